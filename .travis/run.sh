@@ -4,10 +4,8 @@ set -eu
 
 cargo doc --all
 cargo test --all
+cargo test --all --no-default-features
 
-if [[ "$TRAVIS_RUST_VERSION" = "nightly" ]]; then
-    for bench in `basename -s .rs benches/*`; do
-        cargo test --bench $bench
-	done
-    cargo bench
-fi
+for bench in `basename -s .rs benches/*.rs`; do
+    cargo test --bench $bench
+done
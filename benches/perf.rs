@@ -1,171 +1,77 @@
-//! Formatting of a simple ML-like language.
-
-#![feature(test)]
+//! Benchmarks of formatting a simple ML-like language.
 
+#[macro_use]
+extern crate criterion;
 #[macro_use]
 extern crate sparkly;
-extern crate test;
 
-use std::iter::once;
+use criterion::{Bencher, BenchmarkId, Criterion};
+use sparkly::{LayoutMode, RenderOptions, Sparkly};
+
+mod ml;
 
-use sparkly::{Doc, Sparkly};
+use ml::Expr;
 
-#[derive(Clone, Debug)]
-enum Expr {
-    Call(Box<Expr>, Vec<Expr>),
-    If(Box<Expr>, Box<Expr>, Box<Expr>),
-    Lam(Vec<&'static str>, Box<Expr>),
-    Let(bool, &'static str, Box<Expr>, Box<Expr>),
-    Variable(&'static str),
+fn bench_render(b: &mut Bencher, ex: &Expr, mode: LayoutMode) {
+    let opts = RenderOptions::new(80).mode(mode);
+    b.iter(|| ex.to_doc().display_with(opts).to_string())
 }
 
-impl_Display_for_Sparkly!(Expr);
+mod deep_ast {
+    use super::*;
 
-impl Sparkly for Expr {
-    fn to_doc(&self) -> Doc {
-        match *self {
-            Expr::Call(ref func, ref args) => Doc::join(
-                Doc::space(),
-                once(func.to_doc()).chain(args.iter().map(|arg| arg.to_doc())),
-            ).bracket("(", ")"),
-            Expr::If(ref c, ref t, ref e) => Doc::from("if")
-                .append(Doc::space())
-                .append(c.to_doc())
-                .append(Doc::space())
-                .append("then".into())
-                .append(Doc::space())
-                .append(t.to_doc())
-                .append(Doc::space())
-                .append("else".into())
-                .append(Doc::space())
-                .append(e.to_doc()),
-            Expr::Lam(ref args, ref body) => Doc::from("\\")
-                .append(Doc::space().join(args.iter().map(|&arg| Doc::from(arg))))
-                .append(".".into())
-                .append(Doc::space())
-                .append(body.to_doc())
-                .bracket("(", ")"),
-            Expr::Let(rec, name, ref bound, ref body) => if rec {
-                Doc::from("let").append(Doc::nbsp()).append("rec".into())
-            } else {
-                Doc::from("let")
-            }.append(
-                Doc::space()
-                    .append(name.into())
-                    .append(Doc::space())
-                    .group(),
+    /// Makes a call nested `depth` levels deep, with a lambda at each level,
+    /// so that every level adds a couple of groups.
+    pub fn make(depth: usize) -> Expr {
+        (0..depth).fold(Expr::Variable("x"), |ex, _| {
+            Expr::Call(
+                Box::new(Expr::Variable("f")),
+                vec![Expr::Lam(vec!["x"], Box::new(ex)), Expr::Variable("y")],
             )
-                .append("=".into())
-                .append(
-                    Doc::nest(Doc::space().append(bound.to_doc()), 2)
-                        .append(Doc::space())
-                        .append("in".into())
-                        .group(),
-                )
-                .append(Doc::nest(Doc::line().append(body.to_doc()), 2)),
-            Expr::Variable(s) => s.into(),
-        }
+        })
     }
 }
 
-macro_rules! expr {
-    ((call $f:tt $($a:tt)*)) => {
-        Expr::Call(Box::new(expr!($f)), vec![$(expr!($a)),*])
-    };
-    ((if $c:tt $t:tt)) => {
-        Expr::If(Box::new(expr!($c)), Box::new(expr!($t)), None)
-    };
-    ((if $c:tt $t:tt $e:tt)) => {
-        Expr::If(
-            Box::new(expr!($c)),
-            Box::new(expr!($t)),
-            Box::new(expr!($e)),
-        )
-    };
-    ((lam $($n:ident)* . $e:tt)) => {
-        Expr::Lam(vec![$(stringify!($n)),*], Box::new(expr!($e)))
-    };
-    ((let $n:ident $v:tt $e:tt)) => {
-        Expr::Let(false, stringify!($n), Box::new(expr!($v)), Box::new(expr!($e)))
-    };
-    ((letrec $n:ident $v:tt $e:tt)) => {
-        Expr::Let(true, stringify!($n), Box::new(expr!($v)), Box::new(expr!($e)))
-    };
-    ($id:ident) => {
-        Expr::Variable(stringify!($id))
-    };
+fn small_ast(c: &mut Criterion) {
+    let ex = ml::small_ast();
+    let mut group = c.benchmark_group("small_ast");
+    group.bench_function("greedy", |b| bench_render(b, &ex, LayoutMode::Greedy));
+    group.bench_function("optimal", |b| bench_render(b, &ex, LayoutMode::Optimal));
+    group.finish();
 }
 
-mod small_ast {
-    use super::*;
-    use test::Bencher;
-
-    fn make() -> Expr {
-        expr! {
-            (let s (lam x y z . (call x y (call y z)))
-              (let k (lam x y . x)
-                (let i (lam x . x)
-                  (call i s k k i))))
-        }
-    }
-
-    #[bench]
-    fn bench(b: &mut Bencher) {
-        let ex = make();
-        b.iter(|| ex.to_doc().display_opts(80, false).to_string())
-    }
-
-    #[test]
-    fn correctness() {
-        let ex = make();
-        assert_eq!(
-            ex.to_doc().display_opts(80, false).to_string(),
-            concat!(
-                "let s = (\\x y z. (x y (y z))) in\n",
-                "  let k = (\\x y. x) in\n",
-                "    let i = (\\x. x) in\n",
-                "      (i s k k i)"
-            )
-        );
-    }
+fn medium_ast(c: &mut Criterion) {
+    let ex = ml::medium_ast();
+    let mut group = c.benchmark_group("medium_ast");
+    group.bench_function("greedy", |b| bench_render(b, &ex, LayoutMode::Greedy));
+    group.bench_function("optimal", |b| bench_render(b, &ex, LayoutMode::Optimal));
+    group.finish();
 }
 
-mod medium_ast {
-    use super::*;
-    use test::Bencher;
-
-    fn make() -> Expr {
-        expr! {
-            (let s (lam x y z . (call x y (call y z)))
-              (let k (lam x y . x)
-                (let i (lam x . x)
-                  (letrec even (lam x . (if (call eq x zero)
-                                            true
-                                            (call not (call even (call minus x one)))))
-                    (call i (call even five))))))
+fn deep_ast(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deep_ast");
+    for &depth in &[8, 16, 32, 64, 128, 256] {
+        let doc = deep_ast::make(depth).to_doc();
+        // At 80 columns, each level is indented further than the last, so the
+        // output itself grows quadratically. A page wide enough to fit the
+        // whole expression keeps the output (and so the time) linear.
+        group.bench_with_input(BenchmarkId::new("narrow", depth), &doc, |b, doc| {
+            b.iter(|| doc.display_opts(80, false).to_string())
+        });
+        group.bench_with_input(BenchmarkId::new("wide", depth), &doc, |b, doc| {
+            b.iter(|| doc.display_opts(1 << 20, false).to_string())
+        });
+        // The optimal layout is polynomial rather than linear in the depth, so
+        // the deepest expressions are skipped to keep the benchmark quick.
+        if depth <= 64 {
+            let opts = RenderOptions::new(80).mode(LayoutMode::Optimal);
+            group.bench_with_input(BenchmarkId::new("optimal", depth), &doc, |b, doc| {
+                b.iter(|| doc.display_with(opts).to_string())
+            });
         }
     }
-
-    #[bench]
-    fn bench(b: &mut Bencher) {
-        let ex = make();
-        b.iter(|| ex.to_doc().display_opts(80, false).to_string())
-    }
-
-    #[test]
-    fn correctness() {
-        let ex = make();
-        assert_eq!(
-            ex.to_doc().display_opts(80, false).to_string(),
-            concat!(
-                "let s = (\\x y z. (x y (y z))) in\n",
-                "  let k = (\\x y. x) in\n",
-                "    let i = (\\x. x) in\n",
-                "      let rec even =\n",
-                "        (\\x. if (eq x zero) then true else (not (even (minus x one))))\n",
-                "      in\n",
-                "        (i (even five))"
-            )
-        );
-    }
+    group.finish();
 }
+
+criterion_group!(benches, small_ast, medium_ast, deep_ast);
+criterion_main!(benches);
@@ -0,0 +1,293 @@
+//! A `#[derive(Sparkly)]` macro for the `sparkly` pretty-printing library.
+//!
+//! The derived `Sparkly::to_doc` lays values out like `{:?}` when they fit on
+//! a line, and like `{:#?}` when they don't:
+//!
+//! ```text
+//! Point { x: 1, y: 2 }
+//!
+//! Point {
+//!     x: 1,
+//!     y: 2,
+//! }
+//! ```
+//!
+//! Tuple structs and tuple variants are written as `Name(a, b)`, and unit
+//! structs and unit variants as just their name. Every field must implement
+//! `Sparkly`, unless it is skipped or has a `with` function.
+//!
+//! The following attributes are supported on fields:
+//!
+//!  - `#[sparkly(skip)]` leaves the field out.
+//!  - `#[sparkly(rename = "name")]` writes the field under another name.
+//!  - `#[sparkly(style = expr)]` applies the `Style` the expression evaluates
+//!    to to the field's value.
+//!  - `#[sparkly(with = path)]` calls the function at the path, which must
+//!    take a reference to the field and return a `Doc`, instead of
+//!    `Sparkly::to_doc`.
+//!
+//! `#[sparkly(rename = "Name")]` may also be used on the type itself or on
+//! enum variants. `#[sparkly(display)]` on the type also derives a `Display`
+//! impl, like `impl_Display_for_Sparkly!` does.
+
+#![warn(missing_docs)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Expr, Fields, Ident,
+    LitStr, Path, Result,
+};
+
+/// Derives `Sparkly` for a struct or enum. See the crate documentation for the
+/// supported attributes.
+#[proc_macro_derive(Sparkly, attributes(sparkly))]
+pub fn derive_sparkly(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn derive(mut input: DeriveInput) -> Result<TokenStream2> {
+    let attrs = ContainerAttrs::parse(&input.attrs)?;
+    let name = attrs.rename.unwrap_or_else(|| input.ident.to_string());
+
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            let ident = &input.ident;
+            let (pat, doc) = fields(quote!(#ident), &name, &data.fields)?;
+            let pat = pat.unwrap_or_else(|| quote!(#ident));
+            quote! {
+                match *self {
+                    #pat => #doc,
+                }
+            }
+        }
+        Data::Enum(ref data) => {
+            let ident = &input.ident;
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let attrs = VariantAttrs::parse(&variant.attrs)?;
+                    let name = attrs.rename.unwrap_or_else(|| variant.ident.to_string());
+                    let path = {
+                        let variant = &variant.ident;
+                        quote!(#ident::#variant)
+                    };
+                    let (pat, doc) = fields(path.clone(), &name, &variant.fields)?;
+                    let pat = pat.unwrap_or(path);
+                    Ok(quote!(#pat => #doc,))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if arms.is_empty() {
+                quote!(match *self {})
+            } else {
+                quote! {
+                    match *self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+        Data::Union(ref data) => {
+            return Err(Error::new(
+                data.union_token.span,
+                "Sparkly cannot be derived for unions",
+            ))
+        }
+    };
+
+    // Like `#[derive(Debug)]`, every type parameter must implement the trait.
+    let params = input
+        .generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect::<Vec<_>>();
+    {
+        let where_clause = input.generics.make_where_clause();
+        for param in params {
+            where_clause
+                .predicates
+                .push(parse_quote!(#param: ::sparkly::Sparkly));
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let display = if attrs.display {
+        quote! {
+            impl #impl_generics ::std::fmt::Display for #ident #ty_generics #where_clause {
+                fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                    ::sparkly::Sparkly::to_doc(self)
+                        .render_fmt(fmt, ::sparkly::RenderOptions::new(80))
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+    Ok(quote! {
+        impl #impl_generics ::sparkly::Sparkly for #ident #ty_generics #where_clause {
+            fn to_doc(&self) -> ::sparkly::Doc {
+                #body
+            }
+        }
+
+        #display
+    })
+}
+
+/// Returns the pattern that binds the fields of a struct or variant, and the
+/// expression for its `Doc`. The pattern is `None` for unit structs and
+/// variants, which are matched by their path alone.
+fn fields(
+    path: TokenStream2,
+    name: &str,
+    fields: &Fields,
+) -> Result<(Option<TokenStream2>, TokenStream2)> {
+    let mut bindings = Vec::new();
+    let mut docs = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+
+        let binding = Ident::new(&format!("__sparkly_field_{}", i), Span::call_site());
+        let mut doc = match attrs.with {
+            Some(with) => quote!(#with(#binding)),
+            None => quote!(::sparkly::Sparkly::to_doc(#binding)),
+        };
+        if let Some(style) = attrs.style {
+            doc = quote!(#doc.style(#style));
+        }
+        if let Some(ref ident) = field.ident {
+            let label = attrs.rename.unwrap_or_else(|| ident.to_string()) + ": ";
+            doc = quote!(::sparkly::Doc::from(#label).append(#doc));
+            bindings.push(quote!(#ident: ref #binding));
+        } else {
+            let index = syn::Index::from(i);
+            bindings.push(quote!(#index: ref #binding));
+        }
+        docs.push(doc);
+    }
+
+    let pat = match *fields {
+        Fields::Unit => None,
+        _ => Some(quote!(#path { #(#bindings,)* .. })),
+    };
+    let doc = if docs.is_empty() {
+        quote!(::sparkly::Doc::from(#name))
+    } else {
+        let sep = quote!(::sparkly::Doc::from(",").append(::sparkly::Doc::space()));
+        // Like `{:#?}`, the last field is followed by a comma when the fields
+        // are broken onto lines of their own.
+        let trailing = quote!(::sparkly::Doc::from(",").flat_alt(::sparkly::Doc::empty()));
+        let docs = quote!(#sep.join([#(#docs),*]).append(#trailing));
+        match *fields {
+            Fields::Named(_) => {
+                let open = format!("{} {{", name);
+                quote! {
+                    ::sparkly::Doc::from(#open)
+                        .append(::sparkly::Doc::space().append(#docs).nest(4))
+                        .append(::sparkly::Doc::space())
+                        .append(::sparkly::Doc::from("}"))
+                        .group()
+                }
+            }
+            _ => quote! {
+                ::sparkly::Doc::from(#name).append(#docs.bracket("(", ")"))
+            },
+        }
+    };
+    Ok((pat, doc))
+}
+
+/// The attributes on the type being derived for.
+#[derive(Default)]
+struct ContainerAttrs {
+    display: bool,
+    rename: Option<String>,
+}
+
+impl ContainerAttrs {
+    fn parse(attrs: &[Attribute]) -> Result<ContainerAttrs> {
+        let mut out = ContainerAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("sparkly")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("display") {
+                    out.display = true;
+                } else if meta.path.is_ident("rename") {
+                    out.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else {
+                    return Err(meta.error("unknown sparkly attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+/// The attributes on an enum variant.
+#[derive(Default)]
+struct VariantAttrs {
+    rename: Option<String>,
+}
+
+impl VariantAttrs {
+    fn parse(attrs: &[Attribute]) -> Result<VariantAttrs> {
+        let mut out = VariantAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("sparkly")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    out.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else {
+                    return Err(meta.error("unknown sparkly attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+/// The attributes on a field.
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    skip: bool,
+    style: Option<Expr>,
+    with: Option<Path>,
+}
+
+impl FieldAttrs {
+    fn parse(attrs: &[Attribute]) -> Result<FieldAttrs> {
+        let mut out = FieldAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("sparkly")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    out.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("skip") {
+                    out.skip = true;
+                } else if meta.path.is_ident("style") {
+                    out.style = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("with") {
+                    out.with = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("unknown sparkly attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
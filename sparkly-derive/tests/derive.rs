@@ -0,0 +1,153 @@
+extern crate sparkly;
+
+use std::marker::PhantomData;
+
+use sparkly::{Colour, Doc, Sparkly};
+
+fn num(n: &u32) -> Doc {
+    n.to_string().into()
+}
+
+fn word(s: &&'static str) -> Doc {
+    Doc::from(*s)
+}
+
+#[derive(Debug, Sparkly)]
+#[sparkly(display)]
+struct Point {
+    #[sparkly(with = num)]
+    x: u32,
+    #[sparkly(with = num)]
+    y: u32,
+}
+
+#[derive(Debug, Sparkly)]
+struct Pair(Point, Point);
+
+#[derive(Sparkly)]
+struct Unit;
+
+#[derive(Sparkly)]
+#[sparkly(rename = "Config")]
+struct Settings {
+    #[sparkly(rename = "title", with = word)]
+    name: &'static str,
+    #[sparkly(skip)]
+    _cache: Vec<u8>,
+    #[sparkly(style = Colour::Red.normal(), with = num)]
+    level: u32,
+}
+
+#[allow(dead_code)]
+#[derive(Sparkly)]
+enum Shape {
+    Dot,
+    Circle {
+        centre: Point,
+        #[sparkly(with = num)]
+        radius: u32,
+    },
+    #[sparkly(rename = "Seg")]
+    Segment(Point, Point),
+    Hidden(#[sparkly(skip)] u32),
+}
+
+#[derive(Sparkly)]
+struct Wrapper<T> {
+    inner: T,
+    #[sparkly(skip)]
+    _marker: PhantomData<T>,
+}
+
+fn point(x: u32, y: u32) -> Point {
+    Point { x, y }
+}
+
+fn render<T: Sparkly>(value: &T, width: usize) -> String {
+    value.to_doc().display_opts(width, false).to_string()
+}
+
+#[test]
+fn named_struct() {
+    assert_eq!(render(&point(1, 2), 80), "Point { x: 1, y: 2 }");
+    assert_eq!(render(&point(1, 2), 10), "Point {\n    x: 1,\n    y: 2,\n}");
+    assert_eq!(render(&point(1, 2), 0), format!("{:#?}", point(1, 2)));
+}
+
+#[test]
+fn tuple_struct() {
+    let pair = Pair(point(1, 2), point(3, 4));
+    assert_eq!(
+        render(&pair, 80),
+        "Pair(Point { x: 1, y: 2 }, Point { x: 3, y: 4 })"
+    );
+    assert_eq!(
+        render(&pair, 25),
+        "Pair(\n    Point { x: 1, y: 2 },\n    Point { x: 3, y: 4 },\n)"
+    );
+    assert_eq!(render(&pair, 0), format!("{:#?}", pair));
+}
+
+#[test]
+fn unit_struct() {
+    assert_eq!(render(&Unit, 80), "Unit");
+}
+
+#[test]
+fn attributes() {
+    let settings = Settings {
+        name: "demo",
+        _cache: vec![1, 2, 3],
+        level: 3,
+    };
+    assert_eq!(render(&settings, 80), "Config { title: demo, level: 3 }");
+    assert_eq!(
+        settings.to_doc().display_opts(80, true).to_string(),
+        format!(
+            "Config {{ title: demo, level: {}{}",
+            Colour::Red.paint("3"),
+            " }"
+        )
+    );
+}
+
+#[test]
+fn enums() {
+    assert_eq!(render(&Shape::Dot, 80), "Dot");
+    assert_eq!(
+        render(
+            &Shape::Circle {
+                centre: point(0, 0),
+                radius: 5
+            },
+            80
+        ),
+        "Circle { centre: Point { x: 0, y: 0 }, radius: 5 }"
+    );
+    assert_eq!(
+        render(&Shape::Segment(point(0, 0), point(1, 1)), 80),
+        "Seg(Point { x: 0, y: 0 }, Point { x: 1, y: 1 })"
+    );
+    assert_eq!(render(&Shape::Hidden(7), 80), "Hidden");
+}
+
+#[test]
+fn generics() {
+    let wrapper = Wrapper {
+        inner: point(1, 2),
+        _marker: PhantomData,
+    };
+    assert_eq!(
+        render(&wrapper, 80),
+        "Wrapper { inner: Point { x: 1, y: 2 } }"
+    );
+    assert_eq!(
+        render(&wrapper, 20),
+        "Wrapper {\n    inner: Point {\n        x: 1,\n        y: 2,\n    },\n}"
+    );
+}
+
+#[test]
+fn display() {
+    assert_eq!(point(1, 2).to_string(), "Point { x: 1, y: 2 }");
+}
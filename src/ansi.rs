@@ -0,0 +1,354 @@
+//! Rendering `Doc`s as text with ANSI escape codes.
+
+use std::fmt::{Display as DisplayTrait, Formatter, Result as FmtResult, Write as FmtWrite};
+use std::io::{Result as IoResult, Write};
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+
+use ansi_term::{Colour, Style};
+#[cfg(feature = "termion")]
+use termion::{get_tty, terminal_size};
+
+use color::ColorLevel;
+use render::{render, spaces, RenderOptions, Sink};
+use terminal::Terminal;
+use Doc;
+
+impl Doc {
+    /// Returns an object that implements `Display` for `stdout`, with the
+    /// width and colour support detected as described for `Terminal`.
+    pub fn display(&self) -> Display<'_> {
+        self.display_with(Terminal::stdout().options())
+    }
+
+    /// Returns an object that implements `Display` for the given options.
+    pub fn display_opts<C: Into<ColorLevel>>(&self, width: usize, color: C) -> Display<'_> {
+        self.display_with(RenderOptions::new(width).color(color))
+    }
+
+    /// Returns an object that implements `Display` for the given options.
+    pub fn display_with(&self, opts: RenderOptions) -> Display<'_> {
+        Display { doc: self, opts }
+    }
+
+    /// Writes the `Doc` to a `Write` with the given options.
+    ///
+    /// Text is written as soon as the layout of the line it is on has been
+    /// decided, so memory use does not grow with the size of the output.
+    pub fn render_to<W: Write, C: Into<ColorLevel>>(
+        &self,
+        w: W,
+        width: usize,
+        color: C,
+    ) -> IoResult<()> {
+        self.render_with(w, RenderOptions::new(width).color(color))
+    }
+
+    /// Writes the `Doc` to a `Write` with the given options.
+    pub fn render_with<W: Write>(&self, w: W, opts: RenderOptions) -> IoResult<()> {
+        let mut sink = AnsiSink::new(opts.color, IoOut(w));
+        render(&opts, &self.inner, &mut sink)?;
+        sink.finish()
+    }
+
+    /// Returns an object that implements `Display` for the current size of the
+    /// terminal, failing if it cannot be found. Color support is detected as
+    /// described for `Terminal`.
+    #[cfg(feature = "termion")]
+    pub fn display_term(&self) -> IoResult<Display<'_>> {
+        let (width, _) = terminal_size()?;
+        Ok(self.display_opts(width as usize, Terminal::stdout().color()))
+    }
+
+    /// Writes the `Doc` to a `Write`, with the width and colour support of the
+    /// file descriptor it writes to detected as described for `Terminal`.
+    #[cfg(unix)]
+    pub fn write_to<W: AsRawFd + Write>(&self, w: W) -> IoResult<()> {
+        let opts = Terminal::for_fd(&w).options();
+        self.render_with(w, opts)
+    }
+
+    /// Writes the `Doc` to a `Write`, followed by a newline, with the width
+    /// and colour support of the file descriptor it writes to detected as
+    /// described for `Terminal`.
+    #[cfg(unix)]
+    pub fn writeln_to<W: AsRawFd + Write>(&self, mut w: W) -> IoResult<()> {
+        let opts = Terminal::for_fd(&w).options();
+        self.render_with(&mut w, opts)?;
+        w.write_all(b"\n")
+    }
+
+    /// Writes the `Doc` to the terminal.
+    #[cfg(feature = "termion")]
+    pub fn write_to_tty(&self) -> IoResult<()> {
+        let tty = get_tty()?;
+        self.write_to(tty)
+    }
+
+    /// Writes the `Doc` to the terminal, followed by a newline.
+    #[cfg(feature = "termion")]
+    pub fn writeln_to_tty(&self) -> IoResult<()> {
+        let tty = get_tty()?;
+        self.writeln_to(tty)
+    }
+}
+
+/// A helper struct for rendering `Doc`s to an `std::fmt::Write`.
+#[derive(Debug)]
+pub struct Display<'doc> {
+    doc: &'doc Doc,
+    opts: RenderOptions,
+}
+
+impl<'doc> DisplayTrait for Display<'doc> {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        let mut sink = AnsiSink::new(self.opts.color, FmtOut(fmt));
+        render(&self.opts, &self.doc.inner, &mut sink)?;
+        sink.finish()
+    }
+}
+
+/// Returns the OSC 8 escape sequence that starts a hyperlink to the URL, or
+/// ends the current one if the URL is empty.
+fn osc8(url: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\", url)
+}
+
+/// Somewhere an `AnsiSink` can write text.
+trait Out {
+    type Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error>;
+}
+
+/// An `Out` for an `std::fmt::Write`.
+struct FmtOut<W>(W);
+
+impl<W: FmtWrite> Out for FmtOut<W> {
+    type Error = ::std::fmt::Error;
+
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        self.0.write_str(s)
+    }
+}
+
+/// An `Out` for an `std::io::Write`.
+struct IoOut<W>(W);
+
+impl<W: Write> Out for IoOut<W> {
+    type Error = ::std::io::Error;
+
+    fn write_str(&mut self, s: &str) -> IoResult<()> {
+        self.0.write_all(s.as_bytes())
+    }
+}
+
+/// A `Sink` that writes text with ANSI escape codes for styles and links.
+///
+/// The style the terminal is currently in is tracked, so escape codes are only
+/// written when the style changes, rather than around every piece of text. The
+/// style is reset before each newline, so indentation is never styled.
+struct AnsiSink<O> {
+    color: ColorLevel,
+
+    /// The style the terminal is in, after downsampling.
+    current: Style,
+
+    links: Vec<String>,
+    out: O,
+
+    /// The styles of the regions that have been started but not ended, as
+    /// they apply to text in them.
+    styles: Vec<Style>,
+}
+
+impl<O: Out> AnsiSink<O> {
+    fn new(color: ColorLevel, out: O) -> AnsiSink<O> {
+        AnsiSink {
+            color,
+            current: Style::new(),
+            links: Vec::new(),
+            out,
+            styles: Vec::new(),
+        }
+    }
+
+    /// Changes the style of the terminal.
+    fn set_style(&mut self, sty: Style) -> Result<(), O::Error> {
+        if sty != self.current {
+            self.out.write_str(&transition(self.current, sty))?;
+            self.current = sty;
+        }
+        Ok(())
+    }
+
+    /// Resets the style of the terminal, if it was changed. This should be
+    /// called once everything has been rendered.
+    fn finish(&mut self) -> Result<(), O::Error> {
+        self.set_style(Style::new())
+    }
+}
+
+impl<O: Out> Sink<Style> for AnsiSink<O> {
+    type Error = O::Error;
+
+    fn text(&mut self, s: &str) -> Result<(), O::Error> {
+        if self.color != ColorLevel::None && !s.is_empty() {
+            let sty = self.styles.last().cloned().unwrap_or_default();
+            let sty = self.color.downsample(sty);
+            self.set_style(sty)?;
+        }
+        self.out.write_str(s)
+    }
+
+    fn line(&mut self, indent: usize) -> Result<(), O::Error> {
+        // Links are ended before the newline and restarted after the
+        // indentation, so that the indentation isn't part of the link.
+        self.set_style(Style::new())?;
+        let link = self.links.last().filter(|_| self.color != ColorLevel::None);
+        if link.is_some() {
+            self.out.write_str(&osc8(""))?;
+        }
+        self.out.write_str("\n")?;
+        for chunk in spaces(indent) {
+            self.out.write_str(chunk)?;
+        }
+        if let Some(url) = link {
+            self.out.write_str(&osc8(url))?;
+        }
+        Ok(())
+    }
+
+    fn push_annotation(&mut self, sty: &Style) -> Result<(), O::Error> {
+        let outer = self.styles.last().cloned().unwrap_or_default();
+        self.styles.push(compose(outer, *sty));
+        Ok(())
+    }
+
+    fn pop_annotation(&mut self) -> Result<(), O::Error> {
+        self.styles.pop();
+        Ok(())
+    }
+
+    fn push_link(&mut self, url: &str) -> Result<(), O::Error> {
+        self.links.push(url.to_string());
+        if self.color != ColorLevel::None {
+            self.out.write_str(&osc8(url))?;
+        }
+        Ok(())
+    }
+
+    fn pop_link(&mut self) -> Result<(), O::Error> {
+        self.links.pop();
+        if self.color != ColorLevel::None {
+            let url = self.links.last().map_or("", |url| url);
+            self.out.write_str(&osc8(url))?;
+        }
+        Ok(())
+    }
+
+    fn push_reset(&mut self) -> Result<(), O::Error> {
+        self.styles.push(Style::new());
+        Ok(())
+    }
+
+    fn pop_reset(&mut self) -> Result<(), O::Error> {
+        self.styles.pop();
+        Ok(())
+    }
+}
+
+/// Returns the escape codes that change the terminal's style from `from` to
+/// `to`. If `to` only adds to `from`, only the additions are written;
+/// otherwise, the style is reset first.
+///
+/// This is what `Style::infix` is meant to do, but the version of `ansi_term`
+/// used writes the prefix of `from` rather than `to` after a reset.
+fn transition(from: Style, to: Style) -> String {
+    let keeps = |a: Option<Colour>, b: Option<Colour>| a.is_none() || a == b;
+    let adds = keeps(from.foreground, to.foreground)
+        && keeps(from.background, to.background)
+        && (!from.is_bold || to.is_bold)
+        && (!from.is_dimmed || to.is_dimmed)
+        && (!from.is_italic || to.is_italic)
+        && (!from.is_underline || to.is_underline)
+        && (!from.is_blink || to.is_blink)
+        && (!from.is_reverse || to.is_reverse)
+        && (!from.is_hidden || to.is_hidden)
+        && (!from.is_strikethrough || to.is_strikethrough);
+
+    if from == to {
+        String::new()
+    } else if to == Style::new() {
+        from.suffix().to_string()
+    } else if adds {
+        let extra = Style {
+            foreground: to.foreground.filter(|_| from.foreground != to.foreground),
+            background: to.background.filter(|_| from.background != to.background),
+            is_bold: to.is_bold && !from.is_bold,
+            is_dimmed: to.is_dimmed && !from.is_dimmed,
+            is_italic: to.is_italic && !from.is_italic,
+            is_underline: to.is_underline && !from.is_underline,
+            is_blink: to.is_blink && !from.is_blink,
+            is_reverse: to.is_reverse && !from.is_reverse,
+            is_hidden: to.is_hidden && !from.is_hidden,
+            is_strikethrough: to.is_strikethrough && !from.is_strikethrough,
+        };
+        prefix(extra)
+    } else {
+        format!("{}{}", from.suffix(), prefix(to))
+    }
+}
+
+/// Returns the escape codes that start a style.
+///
+/// `ansi_term` writes every palette colour as a 256-colour escape code, which
+/// terminals with only 16 colours don't understand, so the bright colours
+/// `Fixed(8)` to `Fixed(15)` are written with their own SGR codes instead.
+fn prefix(sty: Style) -> String {
+    let bright = |c: Option<Colour>| match c {
+        Some(Colour::Fixed(n @ 8..=15)) => Some(n - 8),
+        _ => None,
+    };
+    let (fg, bg) = (bright(sty.foreground), bright(sty.background));
+    let rest = Style {
+        foreground: sty.foreground.filter(|_| fg.is_none()),
+        background: sty.background.filter(|_| bg.is_none()),
+        ..sty
+    };
+    let codes = fg
+        .map(|n| 90 + n)
+        .into_iter()
+        .chain(bg.map(|n| 100 + n))
+        .map(|code| code.to_string())
+        .collect::<Vec<_>>()
+        .join(";");
+    if codes.is_empty() {
+        rest.prefix().to_string()
+    } else if rest == Style::new() {
+        format!("\x1b[{}m", codes)
+    } else {
+        // Add the codes to the end of the escape code for the other
+        // properties, before its `m`.
+        let rest = rest.prefix().to_string();
+        format!("{};{}m", &rest[..rest.len() - 1], codes)
+    }
+}
+
+/// Returns the style of text styled with `inner` inside a `Doc` styled with
+/// `outer`. The colours and properties set by `inner` take precedence, and the
+/// ones it leaves unset are taken from `outer`.
+pub fn compose(outer: Style, inner: Style) -> Style {
+    Style {
+        foreground: inner.foreground.or(outer.foreground),
+        background: inner.background.or(outer.background),
+        is_bold: inner.is_bold || outer.is_bold,
+        is_dimmed: inner.is_dimmed || outer.is_dimmed,
+        is_italic: inner.is_italic || outer.is_italic,
+        is_underline: inner.is_underline || outer.is_underline,
+        is_blink: inner.is_blink || outer.is_blink,
+        is_reverse: inner.is_reverse || outer.is_reverse,
+        is_hidden: inner.is_hidden || outer.is_hidden,
+        is_strikethrough: inner.is_strikethrough || outer.is_strikethrough,
+    }
+}
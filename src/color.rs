@@ -0,0 +1,162 @@
+//! Colour support levels, and mapping colours to the ones a terminal supports.
+
+#[cfg(feature = "std")]
+use ansi_term::{Colour, Style};
+
+/// How many colours a terminal can display.
+///
+/// The levels are ordered, so a terminal that supports a level also supports
+/// every level below it.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ColorLevel {
+    /// No styling at all; no escape codes are written.
+    None,
+
+    /// The 8 basic colours and their bright versions, as well as bold,
+    /// underline, and so on.
+    Ansi16,
+
+    /// The 256-colour palette.
+    Ansi256,
+
+    /// Any RGB colour.
+    TrueColor,
+}
+
+#[cfg(feature = "std")]
+impl ColorLevel {
+    /// Returns the style with each of its colours replaced by the nearest one
+    /// supported at this level. At `ColorLevel::None`, the colours are
+    /// removed.
+    pub fn downsample(self, sty: Style) -> Style {
+        Style {
+            foreground: sty.foreground.and_then(|c| self.downsample_colour(c)),
+            background: sty.background.and_then(|c| self.downsample_colour(c)),
+            ..sty
+        }
+    }
+
+    fn downsample_colour(self, colour: Colour) -> Option<Colour> {
+        match (self, colour) {
+            (ColorLevel::None, _) => None,
+            (ColorLevel::Ansi16, Colour::Fixed(n)) if n < 8 => Some(BASIC[n as usize]),
+            // Written with the SGR codes for bright colours, rather than as
+            // palette colours.
+            (ColorLevel::Ansi16, Colour::Fixed(n)) if n < 16 => Some(colour),
+            (ColorLevel::Ansi16, Colour::Fixed(_)) | (ColorLevel::Ansi16, Colour::RGB(..)) => {
+                let target = rgb(colour);
+                BASIC
+                    .iter()
+                    .cloned()
+                    .min_by_key(|&c| distance(rgb(c), target))
+            }
+            (ColorLevel::Ansi256, Colour::RGB(r, g, b)) => Some(nearest_256((r, g, b))),
+            _ => Some(colour),
+        }
+    }
+}
+
+/// Enables colour if `true`, without limiting which colours are used.
+impl From<bool> for ColorLevel {
+    fn from(color: bool) -> ColorLevel {
+        if color {
+            ColorLevel::TrueColor
+        } else {
+            ColorLevel::None
+        }
+    }
+}
+
+/// The 8 basic colours, in palette order.
+#[cfg(feature = "std")]
+const BASIC: [Colour; 8] = [
+    Colour::Black,
+    Colour::Red,
+    Colour::Green,
+    Colour::Yellow,
+    Colour::Blue,
+    Colour::Purple,
+    Colour::Cyan,
+    Colour::White,
+];
+
+/// The default xterm values of the first 16 colours of the 256-colour palette.
+#[cfg(feature = "std")]
+const PALETTE_16: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0x80, 0x00, 0x00),
+    (0x00, 0x80, 0x00),
+    (0x80, 0x80, 0x00),
+    (0x00, 0x00, 0x80),
+    (0x80, 0x00, 0x80),
+    (0x00, 0x80, 0x80),
+    (0xc0, 0xc0, 0xc0),
+    (0x80, 0x80, 0x80),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x00, 0x00, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+/// The levels of each channel in the 6x6x6 colour cube of the 256-colour
+/// palette.
+#[cfg(feature = "std")]
+const CUBE: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+
+/// Returns the RGB value of a colour, using the default xterm palette for
+/// colours that are not given as RGB.
+#[cfg(feature = "std")]
+pub fn rgb(colour: Colour) -> (u8, u8, u8) {
+    match colour {
+        Colour::Black => PALETTE_16[0],
+        Colour::Red => PALETTE_16[1],
+        Colour::Green => PALETTE_16[2],
+        Colour::Yellow => PALETTE_16[3],
+        Colour::Blue => PALETTE_16[4],
+        Colour::Purple => PALETTE_16[5],
+        Colour::Cyan => PALETTE_16[6],
+        Colour::White => PALETTE_16[7],
+        Colour::Fixed(n @ 0..=15) => PALETTE_16[n as usize],
+        Colour::Fixed(n @ 16..=231) => {
+            let n = (n - 16) as usize;
+            (CUBE[n / 36], CUBE[n / 6 % 6], CUBE[n % 6])
+        }
+        Colour::Fixed(n) => {
+            let l = 8 + 10 * (n - 232);
+            (l, l, l)
+        }
+        Colour::RGB(r, g, b) => (r, g, b),
+    }
+}
+
+/// Returns the colour from the colour cube or greyscale ramp of the 256-colour
+/// palette nearest to the given RGB value. The first 16 colours are not used,
+/// since terminals often change them.
+#[cfg(feature = "std")]
+fn nearest_256(target: (u8, u8, u8)) -> Colour {
+    let level = |c: u8| {
+        (0..CUBE.len())
+            .min_by_key(|&i| (CUBE[i] as i32 - c as i32).abs())
+            .unwrap() as u8
+    };
+    let cube = Colour::Fixed(16 + 36 * level(target.0) + 6 * level(target.1) + level(target.2));
+
+    let avg = (target.0 as u32 + target.1 as u32 + target.2 as u32) / 3;
+    let grey = Colour::Fixed(232 + (avg.saturating_sub(3) / 10).min(23) as u8);
+
+    if distance(rgb(grey), target) < distance(rgb(cube), target) {
+        grey
+    } else {
+        cube
+    }
+}
+
+/// Returns the squared Euclidean distance between two RGB values.
+#[cfg(feature = "std")]
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
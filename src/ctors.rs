@@ -1,49 +1,74 @@
-use std::iter::FromIterator;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use core::iter::FromIterator;
 
+#[cfg(feature = "std")]
 use ansi_term::Style;
 use itertools::Itertools;
 
-use {Doc, DocInner, Sparkly};
+use {Doc, DocFn, DocInner, MaybeSync, Ptr, Sparkly, Thunk};
 
 // The basic constructors, i.e. functions that don't take self and return Doc.
-impl Doc {
+impl<A> Doc<A> {
+    /// Returns a `Doc` built by calling the function with the column the `Doc`
+    /// starts at.
+    pub fn column<F: DocFn<A>>(f: F) -> Doc<A> {
+        Doc::from(DocInner::Column(Thunk(Ptr::new(f))))
+    }
+
     /// Returns an empty `Doc`.
-    pub fn empty() -> Doc {
+    pub fn empty() -> Doc<A> {
         Doc::from(DocInner::Text("".into()))
     }
 
     /// Expands to a newline, which will never be shortened.
-    pub fn line() -> Doc {
+    pub fn line() -> Doc<A> {
         Doc::from(DocInner::Line(None))
     }
 
     /// Expands to the given string, if it will fit, or a newline if it won't.
-    pub fn line_or(s: &'static str) -> Doc {
+    pub fn line_or(s: &'static str) -> Doc<A> {
         Doc::from(DocInner::Line(Some(s)))
     }
 
+    /// Concatenates `Doc`s like a paragraph, putting spaces between them and
+    /// only breaking lines where the next `Doc` would not fit.
+    pub fn fill_sep<I: IntoIterator<Item = T>, T: Sparkly<A>>(iter: I) -> Doc<A> {
+        Doc::space().fill(iter)
+    }
+
     /// Concatenates `Doc`s, putting newlines between them.
-    pub fn lines<I: IntoIterator<Item = T>, T: Sparkly>(iter: I) -> Doc {
+    pub fn lines<I: IntoIterator<Item = T>, T: Sparkly<A>>(iter: I) -> Doc<A> {
         Doc::from(DocInner::Line(None)).join(iter)
     }
 
+    /// Returns a `Doc` built by calling the function with the current
+    /// indentation.
+    pub fn nesting<F: DocFn<A>>(f: F) -> Doc<A> {
+        Doc::from(DocInner::Nesting(Thunk(Ptr::new(f))))
+    }
+
     /// Returns a `Doc` that always expands to a space.
-    pub fn nbsp() -> Doc {
+    pub fn nbsp() -> Doc<A> {
         Doc::from(" ")
     }
 
     /// Returns a `Doc` that expands to a space if it will fit, or a newline
     /// if it won't.
-    pub fn space() -> Doc {
+    pub fn space() -> Doc<A> {
         Doc::line_or(" ")
     }
 
     /// Returns a `Doc` that represents a point where a line may be split if
     /// needed.
-    pub fn split_point() -> Doc {
+    pub fn split_point() -> Doc<A> {
         Doc::line_or("")
     }
+}
 
+#[cfg(feature = "std")]
+impl Doc {
     /// Constructs a `Doc` containing the given text with the given style.
     pub fn text<T: ToString>(t: T, sty: Style) -> Doc {
         Doc::from(t.to_string()).style(sty)
@@ -51,17 +76,31 @@ impl Doc {
 }
 
 // Constructing combinators, i.e. functions that take self and return Doc.
-impl Doc {
+impl<A> Doc<A> {
+    /// Sets the indentation of the `Doc` to the column it starts at, so that
+    /// every line after the first lines up with the start of the first.
+    pub fn align(self) -> Doc<A>
+    where
+        A: 'static + MaybeSync,
+    {
+        Doc::column(move |k| {
+            let doc = self.clone();
+            Doc::nesting(move |i| doc.clone().nest_by(k as isize - i as isize))
+        })
+    }
+
+    /// Annotates a `Doc`.
+    pub fn annotate(self, a: A) -> Doc<A> {
+        Doc::from(DocInner::Annotate(a, self.inner))
+    }
+
     /// Appends one `Doc` to another.
-    pub fn append(self, right: Doc) -> Doc {
-        Doc::from(DocInner::Append(
-            Box::new(self.inner),
-            Box::new(right.inner),
-        ))
+    pub fn append(self, right: Doc<A>) -> Doc<A> {
+        Doc::from(DocInner::Append(self.inner, right.inner))
     }
 
     /// Brackets a `Doc` between two constant strings.
-    pub fn bracket(self, l: &'static str, r: &'static str) -> Doc {
+    pub fn bracket(self, l: &'static str, r: &'static str) -> Doc<A> {
         Doc::from(l)
             .append(Doc::split_point())
             .append(self)
@@ -71,105 +110,232 @@ impl Doc {
             .group()
     }
 
-    /// Groups the contents of a `Doc`.
-    pub fn group(self) -> Doc {
-        let inner = self.inner;
-        Doc::from(DocInner::Alt(
-            Box::new(inner.clone().flatten()),
-            Box::new(inner),
-        ))
+    /// Joins `Doc`s, placing the `self` `Doc` between them, like `join`. Each
+    /// separator is grouped on its own, so rather than breaking at every
+    /// separator or none of them, as many `Doc`s as fit are placed on each
+    /// line.
+    pub fn fill<I: IntoIterator<Item = T>, T: Sparkly<A>>(self, iter: I) -> Doc<A> {
+        self.group().join(iter)
+    }
+
+    /// Uses the `Doc` when the enclosing group is broken, and `flat` instead
+    /// when it is flattened. For example,
+    /// `Doc::from(",").flat_alt(Doc::empty())` is a trailing comma that only
+    /// appears when a list is split across lines.
+    ///
+    /// `flat` should not be wider than the first line of the `Doc`, or the
+    /// greedy layout may overflow.
+    ///
+    /// This can't be built from the other combinators: `line_or` also
+    /// depends on the enclosing group, but it always ends the line when the
+    /// group is broken.
+    pub fn flat_alt(self, flat: Doc<A>) -> Doc<A> {
+        Doc::from(DocInner::FlatAlt(self.inner, flat.inner))
+    }
+
+    /// Groups the contents of a `Doc`, so that its line breaks are either all
+    /// collapsed (if it fits) or all kept.
+    ///
+    /// The contents are shared rather than copied, so this is cheap.
+    pub fn group(self) -> Doc<A> {
+        Doc::from(DocInner::Group(self.inner))
+    }
+
+    /// Lays out the `Doc` with every line after the first indented by `n`
+    /// columns past the column the `Doc` starts at.
+    pub fn hang(self, n: usize) -> Doc<A>
+    where
+        A: 'static + MaybeSync,
+    {
+        self.nest(n).align()
+    }
+
+    /// Indents the whole `Doc`, including the first line, by `n` columns
+    /// relative to the column it starts at.
+    pub fn indent(self, n: usize) -> Doc<A>
+    where
+        A: 'static + MaybeSync,
+    {
+        Doc::from(" ".repeat(n)).append(self).hang(n)
     }
 
     /// Joins `Doc`s, placing the `self` `Doc` between them.
-    pub fn join<I: IntoIterator<Item = T>, T: Sparkly>(self, iter: I) -> Doc {
+    pub fn join<I: IntoIterator<Item = T>, T: Sparkly<A>>(self, iter: I) -> Doc<A> {
         iter.into_iter()
             .map(|t| t.to_doc())
             .fold1(|l, r| l.append(self.clone()).append(r))
             .unwrap_or_else(Doc::empty)
     }
 
+    /// Makes the `Doc` a hyperlink to the given URL.
+    ///
+    /// With color enabled, links are written as OSC 8 escape sequences, which
+    /// many terminals display as clickable text; otherwise they are dropped.
+    /// HTML output turns them into `<a>` elements.
+    pub fn link<U: Into<Cow<'static, str>>>(self, url: U) -> Doc<A> {
+        Doc::from(DocInner::Link(url.into(), self.inner))
+    }
+
+    /// Tags the `Doc` with an ID. `Doc::source_map` reports where each tagged
+    /// `Doc` ended up in the output; tags are otherwise ignored.
+    pub fn tagged(self, id: usize) -> Doc<A> {
+        Doc::from(DocInner::Tag(id, self.inner))
+    }
+
     /// Nests the `Doc` with the given amount of indentation.
-    pub fn nest(self, n: usize) -> Doc {
-        Doc::from(DocInner::Nest(n, Box::new(self.inner)))
+    pub fn nest(self, n: usize) -> Doc<A> {
+        self.nest_by(n as isize)
     }
 
-    /// Applies a style to a `Doc`.
+    /// Lays out the `Doc` with no indentation, so that every line after the
+    /// first starts in the first column, however deeply the enclosing `Doc`s
+    /// are nested. This suits text whose lines must be written as they are,
+    /// like a multi-line string literal.
+    pub fn dedent(self) -> Doc<A> {
+        self.nest_by(isize::MIN)
+    }
+
+    /// Nests the `Doc` with the given amount of indentation, which may be
+    /// negative.
+    fn nest_by(self, n: isize) -> Doc<A> {
+        Doc::from(DocInner::Nest(n, self.inner))
+    }
+}
+
+impl<A> Doc<A> {
+    /// Stops the styles (or other annotations) of the enclosing `Doc`s from
+    /// applying to the `Doc`. `doc.style(sty).reset_style()` is styled with
+    /// `sty` alone.
+    pub fn reset_style(self) -> Doc<A> {
+        Doc::from(DocInner::Reset(self.inner))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Doc {
+    /// Applies a style to a `Doc`. The style is combined with those of the
+    /// enclosing `Doc`s: the colours and properties it sets take precedence,
+    /// and the ones it leaves unset are inherited.
     pub fn style(self, style: Style) -> Doc {
-        Doc::from(DocInner::Style(style, Box::new(self.inner)))
+        self.annotate(style)
     }
 }
 
-impl DocInner {
-    /// Flattens a document, converting every collapsible line break to the
-    /// appropriate string.
-    fn flatten(self) -> DocInner {
-        match self {
-            DocInner::Append(l, r) => {
-                let l = l.flatten();
-                let r = r.flatten();
-                DocInner::Append(Box::new(l), Box::new(r))
-            }
-            DocInner::Nest(_, x) => x.flatten(),
-            DocInner::Text(s) => DocInner::Text(s),
-            DocInner::Line(Some(s)) => DocInner::Text(s.into()),
-            DocInner::Line(None) => DocInner::Line(None),
-            DocInner::Alt(x, _) => x.flatten(),
-            DocInner::Style(s, d) => DocInner::Style(s, Box::new(d.flatten())),
-        }
+// Transforming combinators, i.e. functions that take &self and return Doc.
+impl<A: 'static> Doc<A> {
+    /// Returns a copy of the `Doc` with every annotation replaced by the
+    /// result of calling the function on it. Parts of the `Doc` that are
+    /// shared are still shared in the result.
+    pub fn map_annotations<B, F>(&self, f: F) -> Doc<B>
+    where
+        B: 'static,
+        F: 'static + Fn(&A) -> B + MaybeSync,
+    {
+        let f = Ptr::new(f);
+        let inner = map_annotations(&self.inner, &f, &mut BTreeMap::new());
+        Doc { inner }
     }
 }
 
-impl From<&'static str> for Doc {
-    fn from(s: &'static str) -> Doc {
-        let inner = if s.contains('\n') {
+fn map_annotations<A, B, F>(
+    doc: &Ptr<DocInner<A>>,
+    f: &Ptr<F>,
+    memo: &mut BTreeMap<*const DocInner<A>, Ptr<DocInner<B>>>,
+) -> Ptr<DocInner<B>>
+where
+    A: 'static,
+    B: 'static,
+    F: 'static + Fn(&A) -> B + MaybeSync,
+{
+    let key = &**doc as *const DocInner<A>;
+    if let Some(mapped) = memo.get(&key) {
+        return mapped.clone();
+    }
+
+    let mapped = match **doc {
+        DocInner::Annotate(ref a, ref x) => DocInner::Annotate(f(a), map_annotations(x, f, memo)),
+        DocInner::Append(ref x, ref y) => {
+            DocInner::Append(map_annotations(x, f, memo), map_annotations(y, f, memo))
+        }
+        DocInner::Column(ref t) => {
+            let (t, f) = (t.clone(), f.clone());
+            DocInner::Column(Thunk(Ptr::new(move |k| {
+                let inner = map_annotations(&t.call(k).inner, &f, &mut BTreeMap::new());
+                Doc { inner }
+            })))
+        }
+        DocInner::FlatAlt(ref x, ref y) => {
+            DocInner::FlatAlt(map_annotations(x, f, memo), map_annotations(y, f, memo))
+        }
+        DocInner::Group(ref x) => DocInner::Group(map_annotations(x, f, memo)),
+        DocInner::Line(s) => DocInner::Line(s),
+        DocInner::Link(ref url, ref x) => DocInner::Link(url.clone(), map_annotations(x, f, memo)),
+        DocInner::Nest(n, ref x) => DocInner::Nest(n, map_annotations(x, f, memo)),
+        DocInner::Nesting(ref t) => {
+            let (t, f) = (t.clone(), f.clone());
+            DocInner::Nesting(Thunk(Ptr::new(move |i| {
+                let inner = map_annotations(&t.call(i).inner, &f, &mut BTreeMap::new());
+                Doc { inner }
+            })))
+        }
+        DocInner::Reset(ref x) => DocInner::Reset(map_annotations(x, f, memo)),
+        DocInner::Tag(id, ref x) => DocInner::Tag(id, map_annotations(x, f, memo)),
+        DocInner::Text(ref s) => DocInner::Text(s.clone()),
+    };
+    let mapped = Ptr::new(mapped);
+    memo.insert(key, mapped.clone());
+    mapped
+}
+
+impl<A> From<&'static str> for Doc<A> {
+    fn from(s: &'static str) -> Doc<A> {
+        if s.contains('\n') {
             let parts = s.rsplit('\n');
             let mut doc = Doc::empty().inner;
             for s in parts {
-                doc = DocInner::Append(
-                    Box::new(DocInner::Append(
-                        Box::new(DocInner::Text(s.into())),
-                        Box::new(DocInner::Line(None)),
+                doc = Ptr::new(DocInner::Append(
+                    Ptr::new(DocInner::Append(
+                        Ptr::new(DocInner::Text(s.into())),
+                        Ptr::new(DocInner::Line(None)),
                     )),
-                    Box::new(doc),
-                );
+                    doc,
+                ));
             }
-            doc
+            Doc { inner: doc }
         } else {
-            DocInner::Text(s.into())
-        };
-        Doc { inner }
+            Doc::from(DocInner::Text(s.into()))
+        }
     }
 }
 
 // TODO: From<AsRef<str>> once specialization is stable
-impl From<String> for Doc {
-    fn from(s: String) -> Doc {
-        let inner = if s.contains('\n') {
+impl<A> From<String> for Doc<A> {
+    fn from(s: String) -> Doc<A> {
+        if s.contains('\n') {
             let parts = s.rsplit('\n');
             let mut doc = Doc::empty().inner;
             for s in parts {
-                doc = DocInner::Append(
-                    Box::new(DocInner::Append(
-                        Box::new(DocInner::Text(s.to_string().into())),
-                        Box::new(DocInner::Line(None)),
+                doc = Ptr::new(DocInner::Append(
+                    Ptr::new(DocInner::Append(
+                        Ptr::new(DocInner::Text(s.to_string().into())),
+                        Ptr::new(DocInner::Line(None)),
                     )),
-                    Box::new(doc),
-                );
+                    doc,
+                ));
             }
-            doc
+            Doc { inner: doc }
         } else {
-            DocInner::Text(s.to_string().into())
-        };
-        Doc { inner }
+            Doc::from(DocInner::Text(s.into()))
+        }
     }
 }
 
-impl FromIterator<Doc> for Doc {
-    fn from_iter<T: IntoIterator<Item = Doc>>(iter: T) -> Doc {
+impl<A> FromIterator<Doc<A>> for Doc<A> {
+    fn from_iter<T: IntoIterator<Item = Doc<A>>>(iter: T) -> Doc<A> {
         let mut inner = Doc::empty().inner;
         for d in iter {
-            inner = DocInner::Append(Box::new(inner), Box::new(d.inner));
+            inner = Ptr::new(DocInner::Append(inner, d.inner));
         }
-        Doc::from(inner)
+        Doc { inner }
     }
 }
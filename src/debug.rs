@@ -0,0 +1,353 @@
+//! Pretty-printing values that only implement `Debug`, by parsing their `{:?}`
+//! output back into a tree of brackets and commas.
+//!
+//! The parser is tolerant: anything it doesn't recognize is kept as text, and
+//! unbalanced brackets are left as they are, so the flat layout of the result
+//! is always the `{:?}` output with runs of whitespace collapsed.
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use {Doc, Sparkly};
+
+impl<A> Doc<A> {
+    /// Returns a `Doc` for the `{:?}` output of a value, broken into lines at
+    /// its brackets and commas when it doesn't fit, like `{:#?}`. Unlike
+    /// `{:#?}`, the parts that do fit stay on one line.
+    pub fn from_debug<T: ?Sized + Debug>(value: &T) -> Doc<A> {
+        let s = format!("{:?}", value);
+        let (items, trailing_comma, _) = items_until(&lex(&s), &mut 0, None, 0);
+
+        let doc = Doc::from(",")
+            .append(Doc::space())
+            .join(items.iter().map(item));
+        if trailing_comma {
+            doc.append(Doc::from(","))
+        } else {
+            doc
+        }
+    }
+}
+
+/// Adapts a value that implements `Debug` to `Sparkly`, using
+/// `Doc::from_debug`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FromDebug<T>(pub T);
+
+impl<A, T: Debug> Sparkly<A> for FromDebug<T> {
+    fn to_doc(&self) -> Doc<A> {
+        Doc::from_debug(&self.0)
+    }
+}
+
+/// A token of `{:?}` output, along with whether whitespace came before it.
+type Spaced<T> = (bool, T);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Close(char),
+    Comma,
+    Open(char),
+    Text(&'a str),
+}
+
+/// The items between the commas of a bracketed list, each of which is a
+/// sequence of nodes.
+type Item<'a> = Vec<Spaced<Node<'a>>>;
+
+#[derive(Debug)]
+enum Node<'a> {
+    Group {
+        open: char,
+        close: Option<char>,
+        padded: bool,
+        items: Vec<Item<'a>>,
+        trailing_comma: bool,
+    },
+    Text(&'a str),
+}
+
+fn lex(s: &str) -> Vec<Spaced<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut space = false;
+    let mut i = 0;
+    while let Some(c) = s[i..].chars().next() {
+        let (token, len) = match c {
+            _ if c.is_whitespace() => {
+                space = true;
+                i += c.len_utf8();
+                continue;
+            }
+            '(' | '[' | '{' => (Token::Open(c), 1),
+            ')' | ']' | '}' => (Token::Close(c), 1),
+            ',' => (Token::Comma, 1),
+            '"' => {
+                let len = string_len(&s[i..]);
+                (Token::Text(&s[i..i + len]), len)
+            }
+            '\'' if char_len(&s[i..]).is_some() => {
+                let len = char_len(&s[i..]).unwrap();
+                (Token::Text(&s[i..i + len]), len)
+            }
+            _ => {
+                let len = atom_len(&s[i..]);
+                (Token::Text(&s[i..i + len]), len)
+            }
+        };
+        tokens.push((space, token));
+        space = false;
+        i += len;
+    }
+    tokens
+}
+
+/// Returns the length of the string literal at the start of `s`, including its
+/// quotes. An unterminated string runs to the end of `s`.
+fn string_len(s: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return i + 1,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+/// Returns the length of the character literal at the start of `s`, if there
+/// is one. A `'` that doesn't start a character literal, such as the one in
+/// `&'static str`, is part of an atom instead.
+fn char_len(s: &str) -> Option<usize> {
+    let mut chars = s.char_indices().skip(1);
+    match chars.next() {
+        // The longest escape is `\u{10ffff}`.
+        Some((_, '\\')) => chars
+            .skip(1)
+            .take(9)
+            .find(|&(_, c)| c == '\'')
+            .map(|(i, _)| i + 1),
+        Some(_) => match chars.next() {
+            Some((i, '\'')) => Some(i + 1),
+            _ => None,
+        },
+        None => None,
+    }
+}
+
+/// Returns the length of the atom at the start of `s`, which runs up to the
+/// next whitespace, bracket, comma, or quote. Generic arguments attached to the
+/// atom, as in `PhantomData<HashMap<u8, u8>>`, are included, even though they
+/// may contain those.
+fn atom_len(s: &str) -> usize {
+    let mut i = 0;
+    while let Some(c) = s[i..].chars().next() {
+        match c {
+            _ if c.is_whitespace() => break,
+            '(' | '[' | '{' | ')' | ']' | '}' | ',' | '"' => break,
+            '<' if i > 0 => i += generics_len(&s[i..]).unwrap_or(1),
+            _ => i += c.len_utf8(),
+        }
+    }
+    i
+}
+
+/// Returns the length of the generic arguments at the start of `s`, from the
+/// `<` to the matching `>`, if they are balanced.
+fn generics_len(s: &str) -> Option<usize> {
+    let mut stack = Vec::new();
+    let mut prev = ' ';
+    let mut i = 0;
+    while let Some(c) = s[i..].chars().next() {
+        match c {
+            '<' | '(' | '[' | '{' => stack.push(c),
+            // The `>` in `->` isn't a closing bracket.
+            '>' if prev == '-' => {}
+            '>' | ')' | ']' | '}' => {
+                if stack.pop() != Some(open_of(c)) {
+                    return None;
+                }
+                if stack.is_empty() {
+                    return Some(i + 1);
+                }
+            }
+            '"' => {
+                i += string_len(&s[i..]);
+                prev = '"';
+                continue;
+            }
+            _ => {}
+        }
+        prev = c;
+        i += c.len_utf8();
+    }
+    None
+}
+
+/// The number of brackets that are parsed into nested groups. Brackets nested
+/// more deeply than this are kept as text, so that neither parsing nor laying
+/// out deeply nested output overflows the stack.
+const MAX_DEPTH: usize = 128;
+
+/// Parses comma-separated items up to the closing bracket `close`, which is
+/// consumed, or up to the end of the tokens or a closing bracket that doesn't
+/// match, which isn't. At the top level, where `close` is `None`, closing
+/// brackets are kept as text. Returns the items, whether there was a trailing
+/// comma, and whether the closing bracket was found.
+///
+/// `depth` is the number of groups the items are in. At `MAX_DEPTH`, brackets
+/// and the commas between them are kept as text.
+fn items_until<'a>(
+    tokens: &[Spaced<Token<'a>>],
+    pos: &mut usize,
+    close: Option<char>,
+    depth: usize,
+) -> (Vec<Item<'a>>, bool, bool) {
+    let mut items = Vec::new();
+    let mut item = Vec::new();
+    let mut trailing_comma = false;
+    let mut closed = false;
+    // The number of brackets kept as text that haven't been closed yet.
+    let mut unclosed = 0;
+    while let Some(&(space, token)) = tokens.get(*pos) {
+        *pos += 1;
+        match token {
+            Token::Open(open) if depth == MAX_DEPTH => {
+                unclosed += 1;
+                item.push((space, Node::Text(open_str(open))));
+            }
+            Token::Close(c) if unclosed > 0 => {
+                unclosed -= 1;
+                item.push((space, Node::Text(close_str(c))));
+            }
+            Token::Comma if unclosed > 0 => item.push((space, Node::Text(","))),
+            Token::Close(c) if Some(c) == close => {
+                closed = true;
+                break;
+            }
+            Token::Close(c) if close.is_none() => item.push((space, Node::Text(close_str(c)))),
+            Token::Close(_) => {
+                *pos -= 1;
+                break;
+            }
+            Token::Comma => {
+                items.push(item);
+                item = Vec::new();
+                trailing_comma = true;
+                continue;
+            }
+            Token::Open(open) => {
+                let close = close_of(open);
+                let padded = tokens.get(*pos).is_some_and(|&(space, _)| space);
+                let (items, trailing_comma, closed) =
+                    items_until(tokens, pos, Some(close), depth + 1);
+                let group = Node::Group {
+                    open,
+                    close: if closed { Some(close) } else { None },
+                    padded,
+                    items,
+                    trailing_comma,
+                };
+                item.push((space, group));
+            }
+            Token::Text(s) => item.push((space, Node::Text(s))),
+        }
+        trailing_comma = false;
+    }
+    if !item.is_empty() {
+        items.push(item);
+    }
+    (items, trailing_comma, closed)
+}
+
+fn item<A>(item: &Item) -> Doc<A> {
+    let mut doc = Doc::empty();
+    for (i, &(space, ref node)) in item.iter().enumerate() {
+        if space && i > 0 {
+            doc = doc.append(Doc::nbsp());
+        }
+        doc = doc.append(match *node {
+            Node::Group {
+                open,
+                close,
+                padded,
+                ref items,
+                trailing_comma,
+            } => group(open, close, padded, items, trailing_comma),
+            Node::Text(s) => Doc::from(s.to_string()),
+        });
+    }
+    doc
+}
+
+fn group<A>(
+    open: char,
+    close: Option<char>,
+    padded: bool,
+    items: &[Item],
+    trailing_comma: bool,
+) -> Doc<A> {
+    let open = Doc::from(open_str(open));
+    if items.is_empty() {
+        return open.append(close.map_or_else(Doc::empty, |c| Doc::from(close_str(c))));
+    }
+
+    let sep = if padded {
+        Doc::space()
+    } else {
+        Doc::split_point()
+    };
+    let mut body = Doc::from(",")
+        .append(Doc::space())
+        .join(items.iter().map(item));
+    body = body.append(if trailing_comma {
+        Doc::from(",")
+    } else if close.is_some() {
+        // Like `{:#?}`, a broken list ends with a comma.
+        Doc::from(",").flat_alt(Doc::empty())
+    } else {
+        Doc::empty()
+    });
+    let doc = open.append(sep.clone().append(body).nest(4));
+    match close {
+        Some(c) => doc.append(sep).append(Doc::from(close_str(c))).group(),
+        // An unclosed bracket runs to the end of the output.
+        None => doc.group(),
+    }
+}
+
+fn close_of(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        _ => '>',
+    }
+}
+
+fn open_of(close: char) -> char {
+    match close {
+        ')' => '(',
+        ']' => '[',
+        '}' => '{',
+        _ => '<',
+    }
+}
+
+fn open_str(open: char) -> &'static str {
+    match open {
+        '(' => "(",
+        '[' => "[",
+        _ => "{",
+    }
+}
+
+fn close_str(close: char) -> &'static str {
+    match close {
+        ')' => ")",
+        ']' => "]",
+        _ => "}",
+    }
+}
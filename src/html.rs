@@ -0,0 +1,335 @@
+//! Rendering `Doc`s as HTML.
+
+use std::fmt::{Display as DisplayTrait, Formatter, Result as FmtResult, Write as FmtWrite};
+
+use ansi_term::{Colour, Style};
+
+use color::rgb;
+use render::{render, spaces, Sink};
+use {Doc, RenderOptions};
+
+/// How the styles in a `Doc` are turned into HTML.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum HtmlStyles {
+    /// Each style becomes a `<span>` with a `style` attribute containing the
+    /// equivalent CSS.
+    #[default]
+    Inline,
+
+    /// Each style becomes a `<span>` with a `class` attribute containing the
+    /// string returned by the function. No span is emitted if the string is
+    /// empty.
+    Classes(fn(&Style) -> String),
+}
+
+impl HtmlStyles {
+    /// Uses class names for styles, with one class for each property of the
+    /// style:
+    ///
+    ///  - `fg-<colour>` and `bg-<colour>` for the foreground and background
+    ///    colours, where `<colour>` is one of `black`, `red`, `green`,
+    ///    `yellow`, `blue`, `purple`, `cyan`, or `white`, a number from 0 to
+    ///    255 for a colour from the 256-colour palette, or `rgb-rrggbb` in
+    ///    hexadecimal for an RGB colour.
+    ///  - `bold`, `dimmed`, `italic`, `underline`, `blink`, `reverse`,
+    ///    `hidden`, and `strikethrough` for the corresponding properties.
+    pub fn classes() -> HtmlStyles {
+        HtmlStyles::Classes(class_names)
+    }
+}
+
+impl Doc {
+    /// Returns an object that implements `Display` by rendering the `Doc` as
+    /// HTML with the given width, with styles as inline CSS.
+    pub fn html(&self, width: usize) -> Html<'_> {
+        self.html_with(RenderOptions::new(width))
+    }
+
+    /// Returns an object that implements `Display` by rendering the `Doc` as
+    /// HTML with the given options, with styles as inline CSS. Styles are
+    /// always rendered, regardless of the `color` option.
+    pub fn html_with(&self, opts: RenderOptions) -> Html<'_> {
+        Html {
+            doc: self,
+            opts,
+            styles: HtmlStyles::Inline,
+        }
+    }
+}
+
+/// A helper struct for rendering `Doc`s as HTML.
+///
+/// The output is meant to be the contents of a `<pre>` element. `<`, `>`, and
+/// `&` are escaped, each styled region becomes a `<span>`, and each link
+/// becomes an `<a>`. Since spans are nested, nested styles are combined by
+/// CSS's inheritance; inside `Doc::reset_style`, the enclosing spans are
+/// closed. Elements are closed before each line break and reopened after the
+/// indentation, so no element ever contains a newline.
+#[derive(Debug)]
+pub struct Html<'doc> {
+    doc: &'doc Doc,
+    opts: RenderOptions,
+    styles: HtmlStyles,
+}
+
+impl<'doc> Html<'doc> {
+    /// Sets how styles are turned into HTML.
+    pub fn styles(self, styles: HtmlStyles) -> Html<'doc> {
+        Html { styles, ..self }
+    }
+}
+
+impl<'doc> DisplayTrait for Html<'doc> {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        let mut sink = HtmlSink {
+            open: Vec::new(),
+            out: fmt,
+            styles: self.styles,
+            written: Vec::new(),
+        };
+        render(&self.opts, &self.doc.inner, &mut sink)
+    }
+}
+
+/// A region that has been started but not ended.
+enum Region {
+    /// A link, with the `href` attribute of its `<a>`.
+    Link(String),
+
+    /// A reset region, inside which the spans of the enclosing regions are
+    /// closed.
+    Reset,
+
+    /// A styled region, with the attribute of its `<span>`. This is `None` if
+    /// the style does not need a span.
+    Span(Option<String>),
+}
+
+/// A `Sink` that writes HTML to an `std::fmt::Write`.
+struct HtmlSink<W> {
+    open: Vec<Region>,
+    out: W,
+    styles: HtmlStyles,
+
+    /// The indices in `open` of the regions whose elements are currently open
+    /// in the output, from the outermost to the innermost. Elements are opened
+    /// lazily, so that no empty elements are written.
+    written: Vec<usize>,
+}
+
+impl<W: FmtWrite> Sink<Style> for HtmlSink<W> {
+    type Error = ::std::fmt::Error;
+
+    fn text(&mut self, s: &str) -> FmtResult {
+        if s.is_empty() {
+            return Ok(());
+        }
+
+        // The regions that should have their elements open: every link, and
+        // every span that isn't inside a reset region.
+        let reset = self
+            .open
+            .iter()
+            .rposition(|r| matches!(*r, Region::Reset))
+            .unwrap_or(0);
+        let wanted: Vec<usize> = (0..self.open.len())
+            .filter(|&i| match self.open[i] {
+                Region::Link(_) => true,
+                Region::Reset => false,
+                Region::Span(ref attr) => attr.is_some() && i >= reset,
+            })
+            .collect();
+
+        let common = self
+            .written
+            .iter()
+            .zip(&wanted)
+            .take_while(|&(a, b)| a == b)
+            .count();
+        while self.written.len() > common {
+            self.close_last()?;
+        }
+        for &i in &wanted[common..] {
+            match self.open[i] {
+                Region::Link(ref href) => write!(self.out, "<a {}>", href)?,
+                Region::Span(Some(ref attr)) => write!(self.out, "<span {}>", attr)?,
+                _ => unreachable!("only links and spans have elements"),
+            }
+        }
+        self.written = wanted;
+        escape(&mut self.out, s)
+    }
+
+    fn line(&mut self, indent: usize) -> FmtResult {
+        while !self.written.is_empty() {
+            self.close_last()?;
+        }
+        self.out.write_char('\n')?;
+        for chunk in spaces(indent) {
+            self.out.write_str(chunk)?;
+        }
+        Ok(())
+    }
+
+    fn push_annotation(&mut self, sty: &Style) -> FmtResult {
+        let (name, value) = match self.styles {
+            HtmlStyles::Inline => ("style", css(sty)),
+            HtmlStyles::Classes(f) => ("class", f(sty)),
+        };
+        let attr = if value.is_empty() {
+            None
+        } else {
+            Some(attr(name, &value)?)
+        };
+        self.open.push(Region::Span(attr));
+        Ok(())
+    }
+
+    fn pop_annotation(&mut self) -> FmtResult {
+        self.pop()
+    }
+
+    fn push_link(&mut self, url: &str) -> FmtResult {
+        let href = attr("href", url)?;
+        self.open.push(Region::Link(href));
+        Ok(())
+    }
+
+    fn pop_link(&mut self) -> FmtResult {
+        self.pop()
+    }
+
+    fn push_reset(&mut self) -> FmtResult {
+        self.open.push(Region::Reset);
+        Ok(())
+    }
+
+    fn pop_reset(&mut self) -> FmtResult {
+        self.pop()
+    }
+}
+
+impl<W: FmtWrite> HtmlSink<W> {
+    /// Closes the innermost element that is open in the output, without
+    /// ending its region.
+    fn close_last(&mut self) -> FmtResult {
+        let i = self.written.pop().expect("an element is open");
+        match self.open[i] {
+            Region::Link(_) => self.out.write_str("</a>"),
+            _ => self.out.write_str("</span>"),
+        }
+    }
+
+    /// Ends the innermost region, closing its element if it is open.
+    fn pop(&mut self) -> FmtResult {
+        if self.written.last() == Some(&(self.open.len() - 1)) {
+            self.close_last()?;
+        }
+        self.open.pop();
+        Ok(())
+    }
+}
+
+/// Returns an attribute with the given name and value.
+fn attr(name: &str, value: &str) -> Result<String, ::std::fmt::Error> {
+    let mut attr = format!("{}=\"", name);
+    escape(&mut attr, value)?;
+    attr.push('"');
+    Ok(attr)
+}
+
+/// Writes a string with the characters HTML treats specially escaped.
+fn escape<W: FmtWrite>(out: &mut W, s: &str) -> FmtResult {
+    let mut rest = s;
+    while let Some(i) = rest.find(&['<', '>', '&', '"'][..]) {
+        out.write_str(&rest[..i])?;
+        out.write_str(match rest.as_bytes()[i] {
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'&' => "&amp;",
+            _ => "&quot;",
+        })?;
+        rest = &rest[i + 1..];
+    }
+    out.write_str(rest)
+}
+
+/// Returns the CSS declarations equivalent to a style.
+fn css(sty: &Style) -> String {
+    let (fg, bg) = if sty.is_reverse {
+        (sty.background, sty.foreground)
+    } else {
+        (sty.foreground, sty.background)
+    };
+
+    let mut decls = Vec::new();
+    if let Some(fg) = fg {
+        decls.push(format!("color:{}", css_colour(fg)));
+    }
+    if let Some(bg) = bg {
+        decls.push(format!("background-color:{}", css_colour(bg)));
+    }
+    if sty.is_bold {
+        decls.push("font-weight:bold".to_string());
+    }
+    if sty.is_dimmed {
+        decls.push("opacity:0.5".to_string());
+    }
+    if sty.is_italic {
+        decls.push("font-style:italic".to_string());
+    }
+    match (sty.is_underline, sty.is_strikethrough) {
+        (true, true) => decls.push("text-decoration:underline line-through".to_string()),
+        (true, false) => decls.push("text-decoration:underline".to_string()),
+        (false, true) => decls.push("text-decoration:line-through".to_string()),
+        (false, false) => {}
+    }
+    if sty.is_hidden {
+        decls.push("visibility:hidden".to_string());
+    }
+    decls.join(";")
+}
+
+/// Returns the CSS colour for a colour.
+fn css_colour(colour: Colour) -> String {
+    let (r, g, b) = rgb(colour);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// The default class names for a style; see `HtmlStyles::classes`.
+fn class_names(sty: &Style) -> String {
+    let mut classes = Vec::new();
+    if let Some(fg) = sty.foreground {
+        classes.push(format!("fg-{}", colour_name(fg)));
+    }
+    if let Some(bg) = sty.background {
+        classes.push(format!("bg-{}", colour_name(bg)));
+    }
+    let flags = [
+        (sty.is_bold, "bold"),
+        (sty.is_dimmed, "dimmed"),
+        (sty.is_italic, "italic"),
+        (sty.is_underline, "underline"),
+        (sty.is_blink, "blink"),
+        (sty.is_reverse, "reverse"),
+        (sty.is_hidden, "hidden"),
+        (sty.is_strikethrough, "strikethrough"),
+    ];
+    classes.extend(flags.iter().filter(|f| f.0).map(|f| f.1.to_string()));
+    classes.join(" ")
+}
+
+fn colour_name(colour: Colour) -> String {
+    match colour {
+        Colour::Black => "black".to_string(),
+        Colour::Red => "red".to_string(),
+        Colour::Green => "green".to_string(),
+        Colour::Yellow => "yellow".to_string(),
+        Colour::Blue => "blue".to_string(),
+        Colour::Purple => "purple".to_string(),
+        Colour::Cyan => "cyan".to_string(),
+        Colour::White => "white".to_string(),
+        Colour::Fixed(n) => n.to_string(),
+        Colour::RGB(r, g, b) => format!("rgb-{:02x}{:02x}{:02x}", r, g, b),
+    }
+}
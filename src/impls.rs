@@ -0,0 +1,208 @@
+//! `Sparkly` impls for standard library types.
+//!
+//! These are laid out like their `Debug` output: like `{:?}` when they fit on
+//! a line, and with one element per line, like `{:#?}`, when they don't.
+
+use alloc::borrow::{Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hash};
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+use {Doc, Sparkly};
+
+/// Separates the elements of a sequence.
+fn comma<A>() -> Doc<A> {
+    Doc::from(",").append(Doc::space())
+}
+
+/// Follows the last element of a sequence, which `{:#?}` ends with a comma.
+fn trailing_comma<A>() -> Doc<A> {
+    Doc::from(",").flat_alt(Doc::empty())
+}
+
+/// Lays out a sequence between brackets.
+fn seq<A, I: IntoIterator<Item = Doc<A>>>(
+    open: &'static str,
+    close: &'static str,
+    iter: I,
+) -> Doc<A> {
+    let mut iter = iter.into_iter().peekable();
+    if iter.peek().is_none() {
+        return Doc::from(open).append(Doc::from(close));
+    }
+    comma()
+        .join(iter)
+        .append(trailing_comma())
+        .bracket(open, close)
+}
+
+/// Lays out a value wrapped in a variant, like `Some(x)`.
+fn variant<A>(name: &'static str, doc: Doc<A>) -> Doc<A> {
+    Doc::from(name).append(doc.append(trailing_comma()).bracket("(", ")"))
+}
+
+/// Lays out a map entry.
+fn entry<A, K: Sparkly<A>, V: Sparkly<A>>(k: &K, v: &V) -> Doc<A> {
+    k.to_doc().append(Doc::from(": ")).append(v.to_doc())
+}
+
+macro_rules! impl_Sparkly_with_Debug {
+    ($($ty:ty),*) => {
+        $(impl<A> Sparkly<A> for $ty {
+            fn to_doc(&self) -> Doc<A> {
+                Doc::from(format!("{:?}", self))
+            }
+        })*
+    };
+}
+
+// Debug already escapes and quotes strings and chars, and never writes a
+// newline for them.
+impl_Sparkly_with_Debug!(
+    bool, char, f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, str,
+    String
+);
+
+#[cfg(feature = "std")]
+impl_Sparkly_with_Debug!(Path, PathBuf);
+
+impl<A> Sparkly<A> for () {
+    fn to_doc(&self) -> Doc<A> {
+        Doc::from("()")
+    }
+}
+
+macro_rules! impl_Sparkly_for_tuple {
+    ($($ty:ident $n:tt),*) => {
+        impl<A, $($ty: Sparkly<A>),*> Sparkly<A> for ($($ty,)*) {
+            fn to_doc(&self) -> Doc<A> {
+                let mut docs = vec![$(self.$n.to_doc()),*];
+                if docs.len() == 1 {
+                    // A tuple of one element has a comma even on one line.
+                    docs.pop().unwrap().append(Doc::from(",")).bracket("(", ")")
+                } else {
+                    seq("(", ")", docs)
+                }
+            }
+        }
+    };
+}
+
+impl_Sparkly_for_tuple!(T0 0);
+impl_Sparkly_for_tuple!(T0 0, T1 1);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2, T3 3);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10);
+impl_Sparkly_for_tuple!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11);
+
+impl<A, T: Sparkly<A>> Sparkly<A> for Option<T> {
+    fn to_doc(&self) -> Doc<A> {
+        match *self {
+            Some(ref t) => variant("Some", t.to_doc()),
+            None => Doc::from("None"),
+        }
+    }
+}
+
+impl<A, T: Sparkly<A>, E: Sparkly<A>> Sparkly<A> for Result<T, E> {
+    fn to_doc(&self) -> Doc<A> {
+        match *self {
+            Ok(ref t) => variant("Ok", t.to_doc()),
+            Err(ref e) => variant("Err", e.to_doc()),
+        }
+    }
+}
+
+impl<A, T: Sparkly<A>> Sparkly<A> for [T] {
+    fn to_doc(&self) -> Doc<A> {
+        seq("[", "]", self.iter().map(Sparkly::to_doc))
+    }
+}
+
+impl<A, T: Sparkly<A>, const N: usize> Sparkly<A> for [T; N] {
+    fn to_doc(&self) -> Doc<A> {
+        self[..].to_doc()
+    }
+}
+
+impl<A, T: Sparkly<A>> Sparkly<A> for Vec<T> {
+    fn to_doc(&self) -> Doc<A> {
+        self[..].to_doc()
+    }
+}
+
+impl<A, T: Sparkly<A>> Sparkly<A> for VecDeque<T> {
+    fn to_doc(&self) -> Doc<A> {
+        seq("[", "]", self.iter().map(Sparkly::to_doc))
+    }
+}
+
+impl<A, K: Sparkly<A>, V: Sparkly<A>> Sparkly<A> for BTreeMap<K, V> {
+    fn to_doc(&self) -> Doc<A> {
+        seq("{", "}", self.iter().map(|(k, v)| entry(k, v)))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A, K, V, S> Sparkly<A> for HashMap<K, V, S>
+where
+    K: Eq + Hash + Sparkly<A>,
+    V: Sparkly<A>,
+    S: BuildHasher,
+{
+    fn to_doc(&self) -> Doc<A> {
+        seq("{", "}", self.iter().map(|(k, v)| entry(k, v)))
+    }
+}
+
+impl<A, T: Sparkly<A>> Sparkly<A> for BTreeSet<T> {
+    fn to_doc(&self) -> Doc<A> {
+        seq("{", "}", self.iter().map(Sparkly::to_doc))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A, T: Eq + Hash + Sparkly<A>, S: BuildHasher> Sparkly<A> for HashSet<T, S> {
+    fn to_doc(&self) -> Doc<A> {
+        seq("{", "}", self.iter().map(Sparkly::to_doc))
+    }
+}
+
+impl<A, T: ?Sized + Sparkly<A>> Sparkly<A> for Box<T> {
+    fn to_doc(&self) -> Doc<A> {
+        (**self).to_doc()
+    }
+}
+
+impl<A, T: ?Sized + Sparkly<A>> Sparkly<A> for Rc<T> {
+    fn to_doc(&self) -> Doc<A> {
+        (**self).to_doc()
+    }
+}
+
+impl<A, T: ?Sized + Sparkly<A>> Sparkly<A> for Arc<T> {
+    fn to_doc(&self) -> Doc<A> {
+        (**self).to_doc()
+    }
+}
+
+impl<'a, A, B: ?Sized + ToOwned + Sparkly<A>> Sparkly<A> for Cow<'a, B> {
+    fn to_doc(&self) -> Doc<A> {
+        (**self).to_doc()
+    }
+}
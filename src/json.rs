@@ -0,0 +1,443 @@
+//! Parsing and pretty-printing JSON text.
+//!
+//! Unlike the `ser` module, this works on JSON text rather than Rust values,
+//! and doesn't need serde. Numbers and strings are kept exactly as they were
+//! written, so reformatting only ever changes the whitespace between tokens
+//! (and, if asked to, the order of keys and trailing commas).
+//!
+//! ```rust
+//! use sparkly::json::{self, Options};
+//!
+//! let value = json::parse(r#"{"b": [1.50, 2e3], "a": "\u0041"}"#).unwrap();
+//! let doc = value.to_doc_with(&Options::new().sort_keys(true));
+//! assert_eq!(
+//!     doc.display_opts(80, false).to_string(),
+//!     r#"{"a": "\u0041", "b": [1.50, 2e3]}"#
+//! );
+//! assert_eq!(
+//!     doc.display_opts(20, false).to_string(),
+//!     "{\n  \"a\": \"\\u0041\",\n  \"b\": [1.50, 2e3]\n}"
+//! );
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use ansi_term::{Colour, Style};
+
+use {Doc, Sparkly};
+
+/// A parsed JSON value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+    /// `null`.
+    Null,
+
+    /// `true` or `false`.
+    Bool(bool),
+
+    /// A number, as it was written.
+    Number(String),
+
+    /// A string, as it was written between its quotes, with any escapes left
+    /// as they are.
+    String(String),
+
+    /// An array.
+    Array(Vec<Value>),
+
+    /// An object, with its keys written like `Value::String`s, in the order
+    /// they appeared. Duplicate keys are kept.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns a `Doc` for the value, laid out with the given options.
+    pub fn to_doc_with(&self, opts: &Options) -> Doc {
+        let styles = &opts.styles;
+        match *self {
+            Value::Null => Doc::text("null", styles.literal),
+            Value::Bool(b) => Doc::text(b, styles.literal),
+            Value::Number(ref n) => Doc::text(n, styles.number),
+            Value::String(ref s) => string(s, styles.string),
+            Value::Array(ref values) => {
+                list(opts, "[", "]", values.iter().map(|v| v.to_doc_with(opts)))
+            }
+            Value::Object(ref entries) => {
+                let mut entries = entries.iter().collect::<Vec<_>>();
+                if opts.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(&b.0));
+                }
+                let entries = entries.into_iter().map(|(k, v)| {
+                    string(k, styles.key)
+                        .append(Doc::text(":", styles.punctuation))
+                        .append(Doc::nbsp())
+                        .append(v.to_doc_with(opts))
+                });
+                list(opts, "{", "}", entries)
+            }
+        }
+    }
+}
+
+impl Sparkly for Value {
+    fn to_doc(&self) -> Doc {
+        self.to_doc_with(&Options::new())
+    }
+}
+
+fn string(s: &str, style: Style) -> Doc {
+    Doc::text(format!("\"{}\"", s), style)
+}
+
+/// Lays out the elements of an array or object between brackets, on one line
+/// if they fit, and with one element per line otherwise.
+fn list<I: IntoIterator<Item = Doc>>(
+    opts: &Options,
+    open: &'static str,
+    close: &'static str,
+    items: I,
+) -> Doc {
+    let punct = opts.styles.punctuation;
+    let mut items = items.into_iter().peekable();
+    if items.peek().is_none() {
+        return Doc::text(open, punct).append(Doc::text(close, punct));
+    }
+
+    let mut body = Doc::text(",", punct).append(Doc::space()).join(items);
+    if opts.trailing_commas {
+        body = body.append(Doc::text(",", punct).flat_alt(Doc::empty()));
+    }
+    Doc::text(open, punct)
+        .append(Doc::split_point().append(body).nest(opts.indent))
+        .append(Doc::split_point())
+        .append(Doc::text(close, punct))
+        .group()
+}
+
+/// Options controlling how JSON is laid out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    indent: usize,
+    sort_keys: bool,
+    styles: Styles,
+    trailing_commas: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options::new()
+    }
+}
+
+impl Options {
+    /// Returns the default options: an indentation of two spaces, keys in
+    /// the order they were written, no trailing commas, and no styles.
+    pub fn new() -> Options {
+        Options {
+            indent: 2,
+            sort_keys: false,
+            styles: Styles::default(),
+            trailing_commas: false,
+        }
+    }
+
+    /// Sets the number of spaces each level of nesting is indented by.
+    pub fn indent(self, indent: usize) -> Options {
+        Options { indent, ..self }
+    }
+
+    /// Sets whether the keys of objects are sorted. Keys are compared as they
+    /// were written, so escapes are not decoded first.
+    pub fn sort_keys(self, sort_keys: bool) -> Options {
+        Options { sort_keys, ..self }
+    }
+
+    /// Sets the styles applied to the output.
+    pub fn styles(self, styles: Styles) -> Options {
+        Options { styles, ..self }
+    }
+
+    /// Sets whether a comma is written after the last element of an array or
+    /// object that is split across lines. The result is not valid JSON, but is
+    /// accepted by many other parsers, such as JSON5's.
+    pub fn trailing_commas(self, trailing_commas: bool) -> Options {
+        Options {
+            trailing_commas,
+            ..self
+        }
+    }
+}
+
+/// The styles applied to the parts of JSON (or RON) output. With the `serde`
+/// feature, these are also the styles of `ser::Serializer`, as `ser::Styles`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Styles {
+    /// The style of object keys and field names.
+    pub key: Style,
+
+    /// The style of `true`, `false`, `null`, and RON's `None` and `()`.
+    pub literal: Style,
+
+    /// The style of numbers.
+    pub number: Style,
+
+    /// The style of brackets, commas, and colons.
+    pub punctuation: Style,
+
+    /// The style of strings and characters.
+    pub string: Style,
+}
+
+impl Styles {
+    /// Returns a colour scheme in the style of `jq`.
+    pub fn colored() -> Styles {
+        Styles {
+            key: Colour::Blue.bold(),
+            literal: Colour::Purple.normal(),
+            number: Colour::Cyan.normal(),
+            punctuation: Style::new().dimmed(),
+            string: Colour::Green.normal(),
+        }
+    }
+}
+
+/// The number of arrays and objects that a value can be nested in, like
+/// serde_json's limit. Deeper values are rejected rather than risk overflowing
+/// the stack, either while parsing or while laying them out.
+const MAX_DEPTH: usize = 128;
+
+/// Parses JSON text, which must contain exactly one value, optionally
+/// surrounded by whitespace. Arrays and objects may be nested up to 128 deep.
+pub fn parse(text: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos == text.len() {
+        Ok(value)
+    } else {
+        Err(parser.error("trailing characters"))
+    }
+}
+
+/// Parses JSON text, and returns a `Doc` for it laid out with the given
+/// options.
+pub fn reformat(text: &str, opts: &Options) -> Result<Doc, ParseError> {
+    parse(text).map(|value| value.to_doc_with(opts))
+}
+
+/// The error returned for invalid JSON.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    /// The byte offset in the text at which the error was found.
+    pub offset: usize,
+
+    msg: &'static str,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "{} at byte {}", self.msg, self.offset)
+    }
+}
+
+impl StdError for ParseError {}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &'static str) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            msg,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, s: &'static str) -> Result<(), ParseError> {
+        if self.text[self.pos..].starts_with(s) {
+            self.pos += s.len();
+            Ok(())
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'n') => self.expect("null").map(|()| Value::Null),
+            Some(b't') => self.expect("true").map(|()| Value::Bool(true)),
+            Some(b'f') => self.expect("false").map(|()| Value::Bool(false)),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'[') => self.nested(Parser::array),
+            Some(b'{') => self.nested(Parser::object),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    /// Parses an array or object with `f`, unless it would be nested too
+    /// deeply.
+    fn nested(
+        &mut self,
+        f: fn(&mut Parser<'a>) -> Result<Value, ParseError>,
+    ) -> Result<Value, ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("recursion limit exceeded"));
+        }
+        self.depth += 1;
+        let value = f(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                self.digits();
+            }
+            _ => return Err(self.error("expected a digit")),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if self.digits() == 0 {
+                return Err(self.error("expected a digit"));
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return Err(self.error("expected a digit"));
+            }
+        }
+        Ok(Value::Number(self.text[start..self.pos].to_string()))
+    }
+
+    /// Parses a string, returning the text between its quotes.
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.pos += 1;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(self.text[start..self.pos - 1].to_string());
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(b'"') | Some(b'\\') | Some(b'/') | Some(b'b') | Some(b'f')
+                        | Some(b'n') | Some(b'r') | Some(b't') => self.pos += 1,
+                        Some(b'u') => {
+                            self.pos += 1;
+                            let hex = self.text.as_bytes()[self.pos..]
+                                .iter()
+                                .take(4)
+                                .take_while(|b| b.is_ascii_hexdigit())
+                                .count();
+                            if hex < 4 {
+                                return Err(self.error("invalid unicode escape"));
+                            }
+                            self.pos += 4;
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                Some(b) if b < 0x20 => return Err(self.error("control character in string")),
+                // Multi-byte characters are skipped a byte at a time; none of
+                // their bytes are quotes or backslashes.
+                Some(_) => self.pos += 1,
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        self.pos += 1;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(values));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, ParseError> {
+        self.pos += 1;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':'"));
+            }
+            self.pos += 1;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(entries));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+}
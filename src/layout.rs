@@ -0,0 +1,103 @@
+//! Laying `Doc`s out as structured lines, rather than as text.
+
+use ansi_term::Style;
+
+use ansi::compose;
+use render::{render, Never, Sink};
+use width::width;
+use {Doc, RenderOptions};
+
+/// A line of a laid-out `Doc`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Line {
+    /// The number of spaces the line is indented by.
+    pub indent: usize,
+
+    /// The text of the line after the indentation, with the style of each part
+    /// of it. Adjacent spans never have the same style, and no span is empty.
+    pub spans: Vec<(String, Style)>,
+}
+
+impl Line {
+    /// Returns the number of columns the line takes up, including its
+    /// indentation.
+    pub fn width(&self) -> usize {
+        self.indent + self.spans.iter().map(|span| width(&span.0)).sum::<usize>()
+    }
+}
+
+impl Doc {
+    /// Lays the `Doc` out with the given width, returning its lines.
+    ///
+    /// This uses the same layout as rendering the `Doc` as text, so the lines
+    /// are exactly those `display_opts` would produce. Text that is not styled
+    /// has the style `Style::new()`.
+    pub fn layout(&self, width: usize) -> Vec<Line> {
+        self.layout_with(RenderOptions::new(width))
+    }
+
+    /// Lays the `Doc` out with the given options, returning its lines. The
+    /// `color` option is ignored.
+    pub fn layout_with(&self, opts: RenderOptions) -> Vec<Line> {
+        let mut sink = LineSink {
+            lines: vec![Line::default()],
+            styles: Vec::new(),
+        };
+        match render(&opts, &self.inner, &mut sink) {
+            Ok(()) => sink.lines,
+            Err(never) => match never {},
+        }
+    }
+}
+
+/// A `Sink` that collects `Line`s.
+struct LineSink {
+    lines: Vec<Line>,
+    styles: Vec<Style>,
+}
+
+impl Sink<Style> for LineSink {
+    type Error = Never;
+
+    fn text(&mut self, s: &str) -> Result<(), Never> {
+        if s.is_empty() {
+            return Ok(());
+        }
+        let sty = self.styles.last().cloned().unwrap_or_default();
+        let spans = &mut self.lines.last_mut().expect("there is always a line").spans;
+        match spans.last_mut() {
+            Some(&mut (ref mut text, last)) if last == sty => text.push_str(s),
+            _ => spans.push((s.to_string(), sty)),
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, indent: usize) -> Result<(), Never> {
+        self.lines.push(Line {
+            indent,
+            spans: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn push_annotation(&mut self, sty: &Style) -> Result<(), Never> {
+        let outer = self.styles.last().cloned().unwrap_or_default();
+        self.styles.push(compose(outer, *sty));
+        Ok(())
+    }
+
+    fn pop_annotation(&mut self) -> Result<(), Never> {
+        self.styles.pop();
+        Ok(())
+    }
+
+    fn push_reset(&mut self) -> Result<(), Never> {
+        self.styles.push(Style::new());
+        Ok(())
+    }
+
+    fn pop_reset(&mut self) -> Result<(), Never> {
+        self.styles.pop();
+        Ok(())
+    }
+}
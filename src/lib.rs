@@ -1,67 +1,249 @@
 //! A pretty-printing library based on Wadler's "A Prettier Printer" with support for ANSI styling.
+//!
+//! `Sparkly` is implemented for standard library types, which are laid out
+//! like their `Debug` output. This includes `str` and `String`, so a string
+//! given where a `Sparkly` value is expected, as in `Doc::lines(vec!["a"])`, is
+//! quoted and escaped. Use `Doc::from` to make a `Doc` of the text itself.
+//!
+//! Without the default `std` feature, the library only needs `alloc`. `Doc`,
+//! its constructors, and `Doc::render_fmt` are available, but ANSI styling,
+//! HTML output, and terminal detection are not, and a `Doc`'s annotations
+//! default to `()` rather than `Style`.
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
+
+#[cfg(feature = "std")]
 extern crate ansi_term;
 extern crate itertools;
 
+#[cfg(all(unix, feature = "std"))]
+extern crate libc;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "derive")]
+extern crate sparkly_derive;
 #[cfg(feature = "termion")]
 extern crate termion;
+#[cfg(feature = "unicode-width")]
+extern crate unicode_segmentation;
+#[cfg(feature = "unicode-width")]
+extern crate unicode_width;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 #[macro_use]
 extern crate proptest;
+#[cfg(all(test, feature = "serde"))]
+extern crate ron;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
 #[macro_use]
 mod macros;
 
+#[cfg(feature = "std")]
+mod ansi;
+mod color;
 mod ctors;
+mod debug;
+#[cfg(feature = "std")]
+mod html;
+mod impls;
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "std")]
+mod layout;
+mod optimal;
 mod render;
+#[cfg(feature = "serde")]
+pub mod ser;
+pub mod sexpr;
+mod source_map;
+#[cfg(feature = "std")]
+mod terminal;
 #[cfg(test)]
 mod tests;
+mod view;
+mod width;
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+#[cfg(not(feature = "sync"))]
+use alloc::rc::Rc;
+#[cfg(feature = "sync")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+use core::mem;
 
+#[cfg(feature = "std")]
 pub use ansi_term::{Colour, Style};
 
-pub use render::Display;
+#[cfg(feature = "std")]
+pub use ansi::Display;
+pub use color::ColorLevel;
+pub use debug::FromDebug;
+#[cfg(feature = "std")]
+pub use html::{Html, HtmlStyles};
+#[cfg(feature = "std")]
+pub use layout::Line;
+pub use render::{LayoutMode, RenderOptions, Sink};
+pub use source_map::{Position, Region, SourceMap};
+#[cfg(feature = "derive")]
+pub use sparkly_derive::Sparkly;
+#[cfg(feature = "std")]
+pub use terminal::Terminal;
+pub use view::{Deferred, View};
+
+#[doc(hidden)]
+pub extern crate core as __core;
+
+/// The annotation type `Doc`s have unless another is given: `Style` with the
+/// `std` feature, and `()` without it.
+#[cfg(feature = "std")]
+type DefaultAnnotation = Style;
+#[cfg(not(feature = "std"))]
+type DefaultAnnotation = ();
+
+/// The pointer type used to share parts of a `Doc`.
+///
+/// This is an `Arc` when the `sync` feature is enabled, so that `Doc` is
+/// `Send` and `Sync`, and an `Rc` otherwise.
+#[cfg(not(feature = "sync"))]
+type Ptr<T> = Rc<T>;
+#[cfg(feature = "sync")]
+type Ptr<T> = Arc<T>;
+
+/// A marker for values that may be stored in a `Doc` behind a pointer.
+///
+/// When the `sync` feature is enabled, this is implemented for every type that
+/// is `Send` and `Sync`. Otherwise, it is implemented for every type.
+#[cfg(not(feature = "sync"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "sync"))]
+impl<T: ?Sized> MaybeSync for T {}
+
+/// A marker for values that may be stored in a `Doc` behind a pointer.
+///
+/// When the `sync` feature is enabled, this is implemented for every type that
+/// is `Send` and `Sync`. Otherwise, it is implemented for every type.
+#[cfg(feature = "sync")]
+pub trait MaybeSync: Send + Sync {}
+#[cfg(feature = "sync")]
+impl<T: ?Sized + Send + Sync> MaybeSync for T {}
 
 /// The type of pretty-printed text.
-#[derive(Clone, Debug, PartialEq)]
-pub struct Doc {
-    inner: DocInner,
+///
+/// Parts of a `Doc` may be annotated with values of type `A`, which renderers
+/// use to decide how that part is displayed. By default, these are ANSI
+/// `Style`s, but a `Doc` can be annotated with anything (such as whether some
+/// text is a keyword or an identifier), and converted to `Style`s with
+/// `Doc::map_annotations` when it is rendered.
+///
+/// Cloning a `Doc` is cheap, since its contents are reference-counted rather
+/// than copied.
+#[derive(Debug, PartialEq)]
+pub struct Doc<A = DefaultAnnotation> {
+    inner: Ptr<DocInner<A>>,
+}
+
+impl<A> Clone for Doc<A> {
+    fn clone(&self) -> Doc<A> {
+        Doc {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<A> From<DocInner<A>> for Doc<A> {
+    fn from(inner: DocInner<A>) -> Doc<A> {
+        Doc {
+            inner: Ptr::new(inner),
+        }
+    }
+}
+
+/// A function that builds a `Doc` from a position, as used by `Doc::column`
+/// and `Doc::nesting`.
+pub trait DocFn<A = DefaultAnnotation>: 'static + Fn(usize) -> Doc<A> + MaybeSync {}
+impl<A, F: 'static + Fn(usize) -> Doc<A> + MaybeSync> DocFn<A> for F {}
+
+/// A shared `DocFn`. Two are only equal if they are the same function.
+struct Thunk<A>(Ptr<dyn DocFn<A>>);
+
+impl<A> Thunk<A> {
+    fn call(&self, n: usize) -> Doc<A> {
+        (self.0)(n)
+    }
 }
 
-impl From<DocInner> for Doc {
-    fn from(inner: DocInner) -> Doc {
-        Doc { inner }
+impl<A> Clone for Thunk<A> {
+    fn clone(&self) -> Thunk<A> {
+        Thunk(self.0.clone())
+    }
+}
+
+impl<A> Debug for Thunk<A> {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.write_str("Thunk(..)")
+    }
+}
+
+impl<A> PartialEq for Thunk<A> {
+    fn eq(&self, other: &Thunk<A>) -> bool {
+        Ptr::ptr_eq(&self.0, &other.0)
     }
 }
 
 /// The actual enum for `Doc`.
 #[derive(Clone, Debug, PartialEq)]
-enum DocInner {
-    /// An alternation between two possible ways to format the same content.
-    ///
-    /// The two `Doc`s must flatten to the same `Doc`.
-    /// No first line of the left `Doc` may be shorter than any first line of
-    /// the right `Doc`.
-    Alt(Box<DocInner>, Box<DocInner>),
+enum DocInner<A> {
+    /// Annotates the `Doc`.
+    Annotate(A, Ptr<DocInner<A>>),
 
     /// A concatenation of two documents, without inserting a break between
     /// them.
-    Append(Box<DocInner>, Box<DocInner>),
+    Append(Ptr<DocInner<A>>, Ptr<DocInner<A>>),
+
+    /// A document built from the column it starts at.
+    Column(Thunk<A>),
+
+    /// A choice between two documents, depending on whether the enclosing
+    /// group is broken (the first) or flattened (the second).
+    FlatAlt(Ptr<DocInner<A>>, Ptr<DocInner<A>>),
+
+    /// An alternation between the flattened form of a document, in which every
+    /// collapsible line break is replaced by its string, and the document
+    /// itself. The flattened form is never built; the renderer lays the
+    /// document out in "flat mode" instead.
+    Group(Ptr<DocInner<A>>),
 
     /// A newline. The parameter is the string the newline collapses to; the
     /// newline is uncollapsible if it is `None`. The string must not contain
     /// `"\n"`.
     Line(Option<&'static str>),
 
+    /// A hyperlink to the given URL. Links take up no space, and are only
+    /// rendered by renderers that support them.
+    Link(Cow<'static, str>, Ptr<DocInner<A>>),
+
     /// Changes the indentation *by* (not to) the given amount, measured in
-    /// spaces. Indentation is inserted _after_ a newline.
-    Nest(usize, Box<DocInner>),
+    /// spaces. Indentation is inserted _after_ a newline. The amount may be
+    /// negative, although the indentation never goes below zero.
+    Nest(isize, Ptr<DocInner<A>>),
+
+    /// A document built from the current indentation.
+    Nesting(Thunk<A>),
+
+    /// Stops the annotations of the enclosing `Doc`s from applying.
+    Reset(Ptr<DocInner<A>>),
 
-    /// Applies a style to the `Doc`.
-    Style(Style, Box<DocInner>),
+    /// Tags the `Doc` with an ID, so that where it ends up in the output can be
+    /// found.
+    Tag(usize, Ptr<DocInner<A>>),
 
     /// A string, which must not contain `"\n"`. This is a `Cow` to avoid
     /// allocating a bunch of `" "` and `""` on the heap.
@@ -70,10 +252,52 @@ enum DocInner {
     Text(Cow<'static, str>),
 }
 
+// A `Doc` built by joining thousands of items is a chain of appends as long as
+// the list, which the default drop glue would walk recursively. Unshared
+// children are detached and dropped one at a time instead.
+impl<A> Drop for DocInner<A> {
+    fn drop(&mut self) {
+        let empty = match *self {
+            DocInner::Column(_) | DocInner::Line(_) | DocInner::Nesting(_) | DocInner::Text(_) => {
+                return
+            }
+            _ => Ptr::new(DocInner::Text(Cow::Borrowed(""))),
+        };
+        let mut stack = Vec::new();
+        take_children(self, &empty, &mut stack);
+        while let Some(doc) = stack.pop() {
+            if let Ok(mut doc) = Ptr::try_unwrap(doc) {
+                take_children(&mut doc, &empty, &mut stack);
+            }
+        }
+    }
+}
+
+/// Moves the children of a node onto the stack, replacing them with `empty`.
+fn take_children<A>(
+    doc: &mut DocInner<A>,
+    empty: &Ptr<DocInner<A>>,
+    stack: &mut Vec<Ptr<DocInner<A>>>,
+) {
+    match *doc {
+        DocInner::Annotate(_, ref mut x)
+        | DocInner::Group(ref mut x)
+        | DocInner::Link(_, ref mut x)
+        | DocInner::Nest(_, ref mut x)
+        | DocInner::Reset(ref mut x)
+        | DocInner::Tag(_, ref mut x) => stack.push(mem::replace(x, empty.clone())),
+        DocInner::Append(ref mut x, ref mut y) | DocInner::FlatAlt(ref mut x, ref mut y) => {
+            stack.push(mem::replace(x, empty.clone()));
+            stack.push(mem::replace(y, empty.clone()));
+        }
+        DocInner::Column(_) | DocInner::Line(_) | DocInner::Nesting(_) | DocInner::Text(_) => {}
+    }
+}
+
 /// A trait for values that are pretty-printable.
-pub trait Sparkly {
+pub trait Sparkly<A = DefaultAnnotation> {
     /// Returns a `Doc` corresponding to the value.
-    fn to_doc(&self) -> Doc;
+    fn to_doc(&self) -> Doc<A>;
 
     // TODO: Once specialization is stable, make a method that (using termion)
     // detects the width and color support of the Write.
@@ -82,14 +306,14 @@ pub trait Sparkly {
     //   fn write_to<W: AsRawFd + Write>(&self) -> IoResult<()>
 }
 
-impl Sparkly for Doc {
-    fn to_doc(&self) -> Doc {
+impl<A> Sparkly<A> for Doc<A> {
+    fn to_doc(&self) -> Doc<A> {
         self.clone()
     }
 }
 
-impl<'a, T: Sparkly> Sparkly for &'a T {
-    fn to_doc(&self) -> Doc {
+impl<A, T: ?Sized + Sparkly<A>> Sparkly<A> for &T {
+    fn to_doc(&self) -> Doc<A> {
         (*self).to_doc()
     }
 }
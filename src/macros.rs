@@ -16,12 +16,12 @@
 #[macro_export]
 macro_rules! impl_Display_for_Sparkly {
     (($($bound:tt)*) $ty:ty) => {
-        impl<$($bound)*> ::std::fmt::Display for $ty
+        impl<$($bound)*> $crate::__core::fmt::Display for $ty
         where
             $ty: $crate::Sparkly,
         {
-            fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                write!(fmt, "{}", $crate::Sparkly::to_doc(self).display_opts(80, false))
+            fn fmt(&self, fmt: &mut $crate::__core::fmt::Formatter) -> $crate::__core::fmt::Result {
+                $crate::Sparkly::to_doc(self).render_fmt(fmt, $crate::RenderOptions::new(80))
             }
         }
     };
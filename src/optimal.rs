@@ -0,0 +1,410 @@
+//! An optimal layout algorithm, in the style of Bernardy's "A Pretty But Not
+//! Greedy Printer".
+//!
+//! Rather than committing to the first layout whose first line fits, this
+//! finds, for each part of the document and each position it may start at,
+//! every layout that is not beaten by another in all of the column it ends at,
+//! the number of characters it puts past the page (or ribbon) width, and the
+//! number of lines it takes. Since none of these can get better by adding more
+//! to the end of a layout, the best layout of the whole document is among those
+//! kept. (This assumes that starting further right never helps, which documents
+//! built with `Doc::column` could in principle violate.)
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::mem;
+
+use render::{nest, pop_region, push_region, Mode, RenderOptions, Sink};
+use width::width;
+use {Doc, DocInner, Ptr};
+
+/// Lays out a `Doc` to minimize overflow past the page and ribbon widths, then
+/// the number of lines, and renders the result to the sink.
+pub fn render<A, S: Sink<A>>(
+    opts: &RenderOptions,
+    doc: &Ptr<DocInner<A>>,
+    sink: &mut S,
+) -> Result<(), S::Error> {
+    let mut resolver = Resolver {
+        built: Vec::new(),
+        memo: BTreeMap::new(),
+        opts,
+    };
+    let measures = resolver.resolve(doc, 0, 0, opts.limit(0), Mode::Break);
+    let best = measures
+        .iter()
+        .min_by_key(|m| m.cost())
+        .expect("every Doc has at least one layout");
+
+    // `Err` holds the node whose region ends at that point.
+    let mut stack: Vec<Result<&Layout<A>, &DocInner<A>>> = vec![Ok(&*best.layout)];
+    while let Some(layout) = stack.pop() {
+        let layout = match layout {
+            Ok(layout) => layout,
+            Err(doc) => {
+                pop_region(sink, doc)?;
+                continue;
+            }
+        };
+        match *layout {
+            Layout::Region(ref doc, ref x) => {
+                push_region(sink, doc)?;
+                stack.push(Err(&**doc));
+                stack.push(Ok(x));
+            }
+            Layout::Append(ref x, ref y) => {
+                stack.push(Ok(y));
+                stack.push(Ok(x));
+            }
+            Layout::Empty => {}
+            Layout::Line(i) => sink.line(i)?,
+            Layout::Text(ref doc) => match **doc {
+                DocInner::Text(ref s) => sink.text(s)?,
+                DocInner::Line(Some(s)) => sink.text(s)?,
+                _ => unreachable!("Layout::Text holds a non-text node"),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// A single concrete layout of (part of) a `Doc`. This is a tree rather than a
+/// list so that layouts can be concatenated without copying.
+enum Layout<A> {
+    Append(Rc<Layout<A>>, Rc<Layout<A>>),
+    Empty,
+    Line(usize),
+
+    /// The region of a `DocInner::Annotate`, `DocInner::Link`,
+    /// `DocInner::Reset`, or `DocInner::Tag`, containing the layout of its
+    /// child.
+    Region(Ptr<DocInner<A>>, Rc<Layout<A>>),
+
+    /// The text of a `DocInner::Text` or a flattened `DocInner::Line`.
+    Text(Ptr<DocInner<A>>),
+}
+
+// Dropping a long chain of appends recursively would overflow the stack, so
+// the children of a layout that isn't shared are dropped from a list instead.
+impl<A> Drop for Layout<A> {
+    fn drop(&mut self) {
+        match *self {
+            Layout::Append(..) | Layout::Region(..) => {}
+            Layout::Empty | Layout::Line(_) | Layout::Text(_) => return,
+        }
+        let empty = Rc::new(Layout::Empty);
+        let mut stack = Vec::new();
+        take_children(self, &empty, &mut stack);
+        while let Some(layout) = stack.pop() {
+            if let Ok(mut layout) = Rc::try_unwrap(layout) {
+                take_children(&mut layout, &empty, &mut stack);
+            }
+        }
+    }
+}
+
+/// Moves the children of a layout onto the stack, replacing them with `empty`.
+fn take_children<A>(layout: &mut Layout<A>, empty: &Rc<Layout<A>>, stack: &mut Vec<Rc<Layout<A>>>) {
+    match *layout {
+        Layout::Append(ref mut x, ref mut y) => {
+            stack.push(mem::replace(x, empty.clone()));
+            stack.push(mem::replace(y, empty.clone()));
+        }
+        Layout::Region(_, ref mut x) => stack.push(mem::replace(x, empty.clone())),
+        Layout::Empty | Layout::Line(_) | Layout::Text(_) => {}
+    }
+}
+
+/// A layout, along with the measurements used to compare it with others.
+struct Measure<A> {
+    /// The column the last line of the layout ends at.
+    col: usize,
+
+    /// The layout itself.
+    layout: Rc<Layout<A>>,
+
+    /// The column text should not go past on the last line of the layout.
+    limit: usize,
+
+    /// The number of newlines in the layout.
+    lines: usize,
+
+    /// The number of characters the layout places past the page width.
+    overflow: usize,
+}
+
+// Deriving this would require `A: Clone`.
+impl<A> Clone for Measure<A> {
+    fn clone(&self) -> Measure<A> {
+        Measure {
+            layout: self.layout.clone(),
+            ..*self
+        }
+    }
+}
+
+impl<A> Measure<A> {
+    fn cost(&self) -> (usize, usize) {
+        (self.overflow, self.lines)
+    }
+
+    fn then(&self, next: &Measure<A>) -> Measure<A> {
+        Measure {
+            col: next.col,
+            layout: Rc::new(Layout::Append(self.layout.clone(), next.layout.clone())),
+            limit: next.limit,
+            lines: self.lines + next.lines,
+            overflow: self.overflow + next.overflow,
+        }
+    }
+}
+
+/// The key a set of measures is memoized under: the address of the node, the
+/// starting column, the indentation, the limit of the starting line, and the
+/// mode.
+type MemoKey<A> = (*const DocInner<A>, usize, usize, usize, Mode);
+
+struct Resolver<'a, A> {
+    /// The `Doc`s built by `DocInner::Column` and `DocInner::Nesting`. These
+    /// are kept alive so that their addresses are not reused while they are
+    /// still in the memo table.
+    built: Vec<Doc<A>>,
+
+    memo: BTreeMap<MemoKey<A>, Rc<Vec<Measure<A>>>>,
+    opts: &'a RenderOptions,
+}
+
+/// A step of `Resolver::resolve`. Rather than recursing, which would overflow
+/// the stack on long documents, the resolver keeps these on a stack of its
+/// own; each step that needs the measures of a child pushes a step to finish
+/// it, followed by the steps that resolve the child.
+///
+/// The steps that finish a node hold the key to memoize its measures under, or
+/// `None` if they don't need to be memoized.
+enum Step<A> {
+    /// Resolves a node at a position, pushing its measures onto the results.
+    /// The flag is whether to look the node up in (and add it to) the memo
+    /// table.
+    Resolve(Ptr<DocInner<A>>, usize, usize, usize, Mode, bool),
+
+    /// Finishes an append, given the measures of its left side: resolves the
+    /// right side after each of them.
+    AppendLeft(Option<MemoKey<A>>, Ptr<DocInner<A>>, usize, Mode),
+
+    /// Finishes an append, given the measures of its left side and those of
+    /// its right side after each one.
+    AppendRight(Option<MemoKey<A>>, Rc<Vec<Measure<A>>>),
+
+    /// Finishes a group, given the measures of its flat and broken layouts.
+    Group(Option<MemoKey<A>>),
+
+    /// Finishes a node whose measures are those of its child.
+    Same(Option<MemoKey<A>>),
+
+    /// Finishes a node whose measures are those of its child, each wrapped in
+    /// the node's region.
+    Wrap(Option<MemoKey<A>>, Ptr<DocInner<A>>),
+}
+
+impl<'a, A> Resolver<'a, A> {
+    /// Returns every layout of `doc` that is worth keeping, when started at
+    /// column `k` with indentation `i` on a line limited to `l` columns.
+    fn resolve(
+        &mut self,
+        doc: &Ptr<DocInner<A>>,
+        k: usize,
+        i: usize,
+        l: usize,
+        mode: Mode,
+    ) -> Rc<Vec<Measure<A>>> {
+        let mut steps = vec![Step::Resolve(doc.clone(), k, i, l, mode, true)];
+        let mut results: Vec<Rc<Vec<Measure<A>>>> = Vec::new();
+        while let Some(step) = steps.pop() {
+            let (key, measures) = match step {
+                Step::Resolve(doc, k, i, l, mode, memo) => {
+                    // Text and lines are cheaper to measure than to look up.
+                    if let Some(measure) = self.leaf(&doc, k, i, l, mode) {
+                        results.push(Rc::new(vec![measure]));
+                        continue;
+                    }
+                    let key = (&*doc as *const DocInner<A>, k, i, l, mode);
+                    if memo {
+                        if let Some(measures) = self.memo.get(&key) {
+                            results.push(measures.clone());
+                            continue;
+                        }
+                    }
+                    self.start(&doc, key, memo, &mut steps);
+                    continue;
+                }
+                Step::AppendLeft(key, y, i, mode) => {
+                    let left = results.pop().expect("missing measures");
+                    steps.push(Step::AppendRight(key, left.clone()));
+                    for mx in left.iter().rev() {
+                        steps.push(Step::Resolve(y.clone(), mx.col, i, mx.limit, mode, true));
+                    }
+                    continue;
+                }
+                Step::AppendRight(key, left) => {
+                    let start = results.len() - left.len();
+                    let mut measures = Vec::new();
+                    for (mx, mys) in left.iter().zip(results.drain(start..)) {
+                        measures.extend(mys.iter().map(|my| mx.then(my)));
+                    }
+                    (key, Rc::new(pareto(measures)))
+                }
+                Step::Group(key) => {
+                    let broken = results.pop().expect("missing measures");
+                    let flat = results.pop().expect("missing measures");
+                    let mut measures = flat.to_vec();
+                    measures.extend(broken.iter().cloned());
+                    (key, Rc::new(pareto(measures)))
+                }
+                Step::Same(key) => (key, results.pop().expect("missing measures")),
+                Step::Wrap(key, doc) => {
+                    let measures = results.pop().expect("missing measures");
+                    let measures = measures
+                        .iter()
+                        .map(|m| Measure {
+                            layout: Rc::new(Layout::Region(doc.clone(), m.layout.clone())),
+                            ..m.clone()
+                        })
+                        .collect();
+                    (key, Rc::new(measures))
+                }
+            };
+            if let Some(key) = key {
+                self.memo.insert(key, measures.clone());
+            }
+            results.push(measures);
+        }
+        results.pop().expect("missing measures")
+    }
+
+    /// Returns the measure of a node without children.
+    fn leaf(
+        &self,
+        doc: &Ptr<DocInner<A>>,
+        k: usize,
+        i: usize,
+        l: usize,
+        mode: Mode,
+    ) -> Option<Measure<A>> {
+        match **doc {
+            DocInner::Text(ref s) => Some(text(doc, s, k, l)),
+            DocInner::Line(Some(s)) if mode == Mode::Flat => Some(text(doc, s, k, l)),
+            DocInner::Line(_) => Some(Measure {
+                col: i,
+                layout: Rc::new(Layout::Line(i)),
+                limit: self.opts.limit(i),
+                lines: 1,
+                overflow: i.saturating_sub(self.opts.width),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Pushes the steps that resolve a node with children.
+    ///
+    /// Every node is resolved at most once at each position, so that the time
+    /// taken is polynomial. Memoizing every node would ensure that, but most
+    /// nodes don't need it: a child resolved at the same position as its
+    /// parent, which has no other parents, is only resolved there when its
+    /// parent is. Leaving these out of the memo table keeps it small; in a
+    /// long list built by `Doc::join`, it holds next to nothing.
+    fn start(
+        &mut self,
+        doc: &Ptr<DocInner<A>>,
+        key: MemoKey<A>,
+        memo: bool,
+        steps: &mut Vec<Step<A>>,
+    ) {
+        let (_, k, i, l, mode) = key;
+        let memo_key = if memo { Some(key) } else { None };
+        let (step, child, child_memo) = match **doc {
+            DocInner::Annotate(_, ref x)
+            | DocInner::Link(_, ref x)
+            | DocInner::Reset(ref x)
+            | DocInner::Tag(_, ref x) => (Step::Wrap(memo_key, doc.clone()), x, shared(x)),
+            DocInner::Append(ref x, ref y) => {
+                (Step::AppendLeft(memo_key, y.clone(), i, mode), x, shared(x))
+            }
+            DocInner::Column(ref f) => {
+                let built = f.call(k);
+                self.built.push(built.clone());
+                steps.push(Step::Same(memo_key));
+                steps.push(Step::Resolve(built.inner, k, i, l, mode, true));
+                return;
+            }
+            DocInner::FlatAlt(_, ref y) if mode == Mode::Flat => {
+                (Step::Same(memo_key), y, shared(y))
+            }
+            DocInner::FlatAlt(ref x, _) => (Step::Same(memo_key), x, shared(x)),
+            DocInner::Group(ref x) if mode == Mode::Flat => (Step::Same(memo_key), x, true),
+            DocInner::Group(ref x) => {
+                steps.push(Step::Group(memo_key));
+                steps.push(Step::Resolve(x.clone(), k, i, l, Mode::Break, true));
+                steps.push(Step::Resolve(x.clone(), k, i, l, Mode::Flat, true));
+                return;
+            }
+            DocInner::Nest(j, ref x) => {
+                steps.push(Step::Same(memo_key));
+                steps.push(Step::Resolve(x.clone(), k, nest(i, j), l, mode, true));
+                return;
+            }
+            DocInner::Nesting(ref f) => {
+                let built = f.call(i);
+                self.built.push(built.clone());
+                steps.push(Step::Same(memo_key));
+                steps.push(Step::Resolve(built.inner, k, i, l, mode, true));
+                return;
+            }
+            DocInner::Line(_) | DocInner::Text(_) => unreachable!("leaves are measured directly"),
+        };
+        steps.push(step);
+        steps.push(Step::Resolve(child.clone(), k, i, l, mode, child_memo));
+    }
+}
+
+/// Returns whether a node may be reached through more than one parent, or is
+/// waiting to be resolved elsewhere.
+fn shared<A>(doc: &Ptr<DocInner<A>>) -> bool {
+    Ptr::strong_count(doc) > 1
+}
+
+/// Returns the measure of the string `s` from the node `doc`, placed at column
+/// `k` on a line limited to `l` columns.
+fn text<A>(doc: &Ptr<DocInner<A>>, s: &str, k: usize, l: usize) -> Measure<A> {
+    let col = k + width(s);
+    let layout = if s.is_empty() {
+        Layout::Empty
+    } else {
+        Layout::Text(doc.clone())
+    };
+    Measure {
+        col,
+        layout: Rc::new(layout),
+        limit: l,
+        lines: 0,
+        overflow: col.saturating_sub(l) - k.saturating_sub(l),
+    }
+}
+
+/// Removes every measure for which another with the same limit ends at a
+/// column no later and costs no more.
+fn pareto<A>(mut measures: Vec<Measure<A>>) -> Vec<Measure<A>> {
+    measures.sort_by_key(|m| (m.limit, m.col, m.cost()));
+    let mut best: Option<(usize, (usize, usize))> = None;
+    measures.retain(|m| {
+        let keep = match best {
+            Some((limit, cost)) => m.limit != limit || m.cost() < cost,
+            None => true,
+        };
+        if keep {
+            best = Some((m.limit, m.cost()));
+        }
+        keep
+    });
+    measures
+}
@@ -1,187 +1,408 @@
-use std::fmt::{Display as DisplayTrait, Formatter, Result as FmtResult};
-#[cfg(feature = "termion")]
-use std::io::{stdout, Result as IoResult, Write};
-#[cfg(feature = "termion")]
-use std::os::unix::io::AsRawFd;
-
-use ansi_term::Style;
-#[cfg(feature = "termion")]
-use termion::{get_tty, is_tty, terminal_size};
-
-use {Doc, DocInner};
-
-impl Doc {
-    /// Returns an object that implements `Display` for the current size of the
-    /// terminal, or 80 columns wide if it cannot be detected. Color is enabled
-    /// if `stdout` is a TTY.
-    ///
-    /// Terminal width detection requires the `termion` feature.
-    pub fn display(&self) -> Display {
-        #[cfg(feature = "termion")]
-        let width = terminal_size().map(|(w, _)| w).unwrap_or(80);
-        #[cfg(not(feature = "termion"))]
-        let width = 80;
-
-        #[cfg(feature = "termion")]
-        let color = is_tty(&stdout());
-        #[cfg(not(feature = "termion"))]
-        let color = false;
-
-        self.display_opts(width as usize, color)
-    }
-
-    /// Returns an object that implements `Display` for the given options.
-    pub fn display_opts(&self, width: usize, color: bool) -> Display {
-        Display {
-            color,
-            doc: &self.inner,
+use core::fmt::{Result as FmtResult, Write as FmtWrite};
+
+use color::ColorLevel;
+use optimal;
+use width::width;
+use {Doc, DocInner, Ptr};
+
+/// Options controlling how a `Doc` is laid out and rendered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderOptions {
+    pub(crate) color: ColorLevel,
+    pub(crate) mode: LayoutMode,
+    pub(crate) ribbon: Option<usize>,
+    pub(crate) width: usize,
+}
+
+impl RenderOptions {
+    /// Returns the default options for a page of the given width: no color,
+    /// no ribbon width, and the greedy layout.
+    pub fn new(width: usize) -> RenderOptions {
+        RenderOptions {
+            color: ColorLevel::None,
+            mode: LayoutMode::Greedy,
+            ribbon: None,
             width,
         }
     }
 
-    /// Returns an object that implements `Display` for the current size of the
-    /// terminal. Color support is assumed if `stdout` is a TTY.
-    #[cfg(feature = "termion")]
-    pub fn display_term(&self) -> IoResult<Display> {
-        let (width, _) = terminal_size()?;
-        Ok(self.display_opts(width as usize, is_tty(&stdout())))
+    /// Sets which colours may be used when rendering styles as ANSI escape
+    /// codes. Colours the level does not support are replaced with the nearest
+    /// one it does.
+    ///
+    /// Passing `true` enables styles without limiting the colours used, and
+    /// passing `false` disables them.
+    pub fn color<C: Into<ColorLevel>>(self, color: C) -> RenderOptions {
+        RenderOptions {
+            color: color.into(),
+            ..self
+        }
     }
 
-    /// Writes the `Doc` to a `Write` for the current size of the terminal.
-    /// Color support is assumed if the `Write` is a TTY.
-    #[cfg(feature = "termion")]
-    pub fn write_to<W: AsRawFd + Write>(&self, mut w: W) -> IoResult<()> {
-        let (width, _) = terminal_size()?;
-        let disp = self.display_opts(width as usize, is_tty(&w));
-        write!(w, "{}", disp)
+    /// Sets the algorithm used to lay the `Doc` out.
+    pub fn mode(self, mode: LayoutMode) -> RenderOptions {
+        RenderOptions { mode, ..self }
     }
 
-    /// Writes the `Doc` to a `Write` for the current size of the terminal,
-    /// followed by a newline. Color support is assumed if the `Write` is a
-    /// TTY.
-    #[cfg(feature = "termion")]
-    pub fn writeln_to<W: AsRawFd + Write>(&self, mut w: W) -> IoResult<()> {
-        let (width, _) = terminal_size()?;
-        let disp = self.display_opts(width as usize, is_tty(&w));
-        writeln!(w, "{}", disp)
+    /// Sets the ribbon width, which is the most characters a line may contain,
+    /// not counting its indentation. Lines are still limited to the width of
+    /// the page as well.
+    pub fn ribbon(self, ribbon: usize) -> RenderOptions {
+        RenderOptions {
+            ribbon: Some(ribbon),
+            ..self
+        }
     }
 
-    /// Writes the `Doc` to the terminal. Color support is assumed.
-    #[cfg(feature = "termion")]
-    pub fn write_to_tty(&self) -> IoResult<()> {
-        let tty = get_tty()?;
-        self.write_to(tty)
+    /// Sets the width of the page.
+    pub fn width(self, width: usize) -> RenderOptions {
+        RenderOptions { width, ..self }
     }
 
-    /// Writes the `Doc` to the terminal, followed by a newline.
-    #[cfg(feature = "termion")]
-    pub fn writeln_to_tty(&self) -> IoResult<()> {
-        let tty = get_tty()?;
-        self.writeln_to(tty)
+    /// Returns the column text should not go past on a line with the given
+    /// indentation, taking both the page and ribbon widths into account.
+    pub(crate) fn limit(&self, indent: usize) -> usize {
+        match self.ribbon {
+            Some(ribbon) => self.width.min(indent.saturating_add(ribbon)),
+            None => self.width,
+        }
     }
 }
 
-/// A helper struct for rendering `Doc`s to an `std::fmt::Write`.
-#[derive(Debug)]
-pub struct Display<'doc> {
-    color: bool,
-    doc: &'doc DocInner,
-    width: usize,
+/// The algorithm used to choose between the possible layouts of a `Doc`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LayoutMode {
+    /// Wadler's algorithm, which lays each group out flat if everything up to
+    /// the next possible line break fits. This runs in linear time, and can
+    /// render output before the whole document has been laid out.
+    Greedy,
+
+    /// Bernardy's algorithm, from "A Pretty But Not Greedy Printer", which
+    /// chooses the layout with the fewest characters past the page width, and
+    /// then the fewest lines. This is considerably slower than `Greedy`, and
+    /// has to lay the whole document out before rendering any of it.
+    Optimal,
 }
 
-impl<'doc> DisplayTrait for Display<'doc> {
-    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
-        for r in best(self.width, 0, vec![(0, Style::new(), &self.doc)]) {
-            match r {
-                RenderDoc::Line(i) => {
-                    fmt.write_str("\n")?;
-                    for _ in 0..i {
-                        fmt.write_str(" ")?;
-                    }
-                }
-                RenderDoc::Text(s, sty) => if self.color {
-                    write!(fmt, "{}", sty.paint(s))?;
-                } else {
-                    fmt.write_str(s)?;
-                },
-            }
+impl<A> Doc<A> {
+    /// Writes the `Doc` to a `fmt::Write` with the given options, as
+    /// plain text. Annotations, links, and the `color` option are ignored.
+    ///
+    /// Unlike `display_with`, this is available without the `std` feature.
+    pub fn render_fmt<W: FmtWrite>(&self, w: W, opts: RenderOptions) -> FmtResult {
+        render(&opts, &self.inner, &mut PlainSink(w))
+    }
+
+    /// Lays out the `Doc` with the given options, sending the result to a
+    /// `Sink`. This allows rendering a `Doc` with any annotation type, without
+    /// first converting the annotations to `Style`s.
+    pub fn render_sink<S: Sink<A>>(
+        &self,
+        opts: RenderOptions,
+        sink: &mut S,
+    ) -> Result<(), S::Error> {
+        render(&opts, &self.inner, sink)
+    }
+}
+
+/// Somewhere rendered text can be sent.
+pub trait Sink<A> {
+    /// The type of errors that can occur when writing.
+    type Error;
+
+    /// Writes a string, which will not contain `"\n"`.
+    fn text(&mut self, s: &str) -> Result<(), Self::Error>;
+
+    /// Writes a newline, followed by the given amount of indentation.
+    fn line(&mut self, indent: usize) -> Result<(), Self::Error>;
+
+    /// Starts a region with the given annotation. Regions are properly
+    /// nested, and may span multiple lines.
+    fn push_annotation(&mut self, a: &A) -> Result<(), Self::Error>;
+
+    /// Ends the most recently started region.
+    fn pop_annotation(&mut self) -> Result<(), Self::Error>;
+
+    /// Starts a hyperlink to the given URL. Like annotated regions, links are
+    /// properly nested, and may span multiple lines.
+    ///
+    /// By default, links are ignored.
+    fn push_link(&mut self, url: &str) -> Result<(), Self::Error> {
+        let _ = url;
+        Ok(())
+    }
+
+    /// Ends the most recently started hyperlink.
+    fn pop_link(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Starts a region tagged with the given ID. Like annotated regions,
+    /// tagged regions are properly nested, and may span multiple lines.
+    ///
+    /// By default, tags are ignored.
+    fn push_tag(&mut self, id: usize) -> Result<(), Self::Error> {
+        let _ = id;
+        Ok(())
+    }
+
+    /// Ends the most recently started tagged region.
+    fn pop_tag(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Starts a region in which the annotations of the enclosing regions no
+    /// longer apply. Like annotated regions, these are properly nested with
+    /// other regions, and may span multiple lines.
+    ///
+    /// By default, this is ignored.
+    fn push_reset(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Ends the most recently started reset region, so the annotations that
+    /// applied before it apply again.
+    fn pop_reset(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// A `Sink` that writes plain text to a `fmt::Write`.
+struct PlainSink<W>(W);
+
+impl<A, W: FmtWrite> Sink<A> for PlainSink<W> {
+    type Error = ::core::fmt::Error;
+
+    fn text(&mut self, s: &str) -> FmtResult {
+        self.0.write_str(s)
+    }
+
+    fn line(&mut self, indent: usize) -> FmtResult {
+        self.0.write_char('\n')?;
+        for chunk in spaces(indent) {
+            self.0.write_str(chunk)?;
         }
         Ok(())
     }
+
+    fn push_annotation(&mut self, _: &A) -> FmtResult {
+        Ok(())
+    }
+
+    fn pop_annotation(&mut self) -> FmtResult {
+        Ok(())
+    }
 }
 
-enum RenderDoc<'doc> {
-    Line(usize),
-    Text(&'doc str, Style),
+/// An error that can never occur, for `Sink`s that cannot fail.
+pub enum Never {}
+
+/// Splits `n` spaces into a few larger strings, to avoid writing them one at a
+/// time.
+pub fn spaces(n: usize) -> impl Iterator<Item = &'static str> {
+    const SPACES: &str = "                                                                ";
+    let full = n / SPACES.len();
+    let rest = n % SPACES.len();
+    (0..full)
+        .map(|_| SPACES)
+        .chain(Some(&SPACES[..rest]).filter(|s| !s.is_empty()))
 }
 
-// `w` is the width of the terminal, `k` is the number of characters already
-// placed.
-fn best<'doc>(
-    w: usize,
-    mut k: usize,
-    mut stack: Vec<(usize, Style, &'doc DocInner)>,
-) -> Vec<RenderDoc<'doc>> {
-    let mut rendered = Vec::new();
-    while let Some((i, sty, doc)) = stack.pop() {
+/// Whether a part of a `Doc` is being laid out with its collapsible line
+/// breaks kept or collapsed.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Mode {
+    Break,
+    Flat,
+}
+
+/// Lays out and renders a `Doc` with the algorithm given in the options.
+pub fn render<A, S: Sink<A>>(
+    opts: &RenderOptions,
+    doc: &Ptr<DocInner<A>>,
+    sink: &mut S,
+) -> Result<(), S::Error> {
+    match opts.mode {
+        LayoutMode::Greedy => greedy(opts, doc, sink),
+        LayoutMode::Optimal => optimal::render(opts, doc, sink),
+    }
+}
+
+/// Changes the indentation `i` by `j`, without going below zero.
+pub fn nest(i: usize, j: isize) -> usize {
+    if j < 0 {
+        i.saturating_sub(j.unsigned_abs())
+    } else {
+        i + j as usize
+    }
+}
+
+/// An entry on the stack of things left to lay out.
+enum Cmd<A> {
+    /// A `Doc`, with the indentation and mode it is laid out with.
+    Doc(usize, Mode, Ptr<DocInner<A>>),
+
+    /// The end of the region of a `DocInner::Annotate`, `DocInner::Link`,
+    /// `DocInner::Reset`, or `DocInner::Tag`.
+    Pop(Ptr<DocInner<A>>),
+}
+
+/// Starts the region of a `DocInner::Annotate`, `DocInner::Link`,
+/// `DocInner::Reset`, or `DocInner::Tag`.
+pub fn push_region<A, S: Sink<A>>(sink: &mut S, doc: &DocInner<A>) -> Result<(), S::Error> {
+    match *doc {
+        DocInner::Annotate(ref a, _) => sink.push_annotation(a),
+        DocInner::Link(ref url, _) => sink.push_link(url),
+        DocInner::Reset(_) => sink.push_reset(),
+        DocInner::Tag(id, _) => sink.push_tag(id),
+        _ => unreachable!("push_region called on a non-region node"),
+    }
+}
+
+/// Ends the region of a `DocInner::Annotate`, `DocInner::Link`,
+/// `DocInner::Reset`, or `DocInner::Tag`.
+pub fn pop_region<A, S: Sink<A>>(sink: &mut S, doc: &DocInner<A>) -> Result<(), S::Error> {
+    match *doc {
+        DocInner::Annotate(..) => sink.pop_annotation(),
+        DocInner::Link(..) => sink.pop_link(),
+        DocInner::Reset(_) => sink.pop_reset(),
+        DocInner::Tag(..) => sink.pop_tag(),
+        _ => unreachable!("pop_region called on a non-region node"),
+    }
+}
+
+// Each group is resolved as soon as it is reached, by checking whether its
+// flattened form (followed by whatever else is on the stack) fits on the
+// current line. Once that choice is made it is never revisited, so text can be
+// sent to the sink immediately, and the whole layout is linear in the size of
+// the document.
+fn greedy<A, S: Sink<A>>(
+    opts: &RenderOptions,
+    doc: &Ptr<DocInner<A>>,
+    sink: &mut S,
+) -> Result<(), S::Error> {
+    // `k` is the number of characters already placed on the current line, and
+    // `l` is the indentation the current line started with.
+    let mut k = 0;
+    let mut l = 0;
+    let mut stack = vec![Cmd::Doc(0, Mode::Break, doc.clone())];
+    while let Some(cmd) = stack.pop() {
+        let (i, mode, doc) = match cmd {
+            Cmd::Doc(i, mode, doc) => (i, mode, doc),
+            Cmd::Pop(doc) => {
+                pop_region(sink, &doc)?;
+                continue;
+            }
+        };
         match *doc {
+            DocInner::Annotate(_, ref x)
+            | DocInner::Link(_, ref x)
+            | DocInner::Reset(ref x)
+            | DocInner::Tag(_, ref x) => {
+                push_region(sink, &doc)?;
+                stack.push(Cmd::Pop(doc.clone()));
+                stack.push(Cmd::Doc(i, mode, x.clone()));
+            }
             DocInner::Append(ref x, ref y) => {
-                stack.push((i, sty, y));
-                stack.push((i, sty, x));
+                stack.push(Cmd::Doc(i, mode, y.clone()));
+                stack.push(Cmd::Doc(i, mode, x.clone()));
+            }
+            DocInner::Column(ref f) => {
+                stack.push(Cmd::Doc(i, mode, f.call(k).inner));
+            }
+            DocInner::FlatAlt(ref x, ref y) => {
+                let alt = if mode == Mode::Flat { y } else { x };
+                stack.push(Cmd::Doc(i, mode, alt.clone()));
+            }
+            DocInner::Group(ref x) => {
+                let mode = if mode == Mode::Flat || fits(opts.limit(l), k, i, x, &stack) {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.push(Cmd::Doc(i, mode, x.clone()));
             }
             DocInner::Nest(j, ref x) => {
-                stack.push((i + j, sty, x));
+                stack.push(Cmd::Doc(nest(i, j), mode, x.clone()));
+            }
+            DocInner::Nesting(ref f) => {
+                stack.push(Cmd::Doc(i, mode, f.call(i).inner));
             }
             DocInner::Text(ref s) => {
-                k += s.len();
-                rendered.push(RenderDoc::Text(s, sty));
+                k += width(s);
+                sink.text(s)?;
+            }
+            DocInner::Line(Some(s)) if mode == Mode::Flat => {
+                k += width(s);
+                sink.text(s)?;
             }
             DocInner::Line(_) => {
                 k = i;
-                rendered.push(RenderDoc::Line(i));
-            }
-            DocInner::Alt(ref x, ref y) => {
-                // TODO: There ought to be a better way to do this.
-                let mut l = stack.clone();
-                l.push((i, sty, x));
-                let mut r = stack;
-                r.push((i, sty, y));
-                rendered.extend(better(w, k, best(w, k, l), best(w, k, r)));
-                return rendered;
-            }
-            DocInner::Style(sty, ref x) => {
-                stack.push((i, sty, x));
+                l = i;
+                sink.line(i)?;
             }
         }
     }
-    rendered
-}
-
-fn better<'doc>(
-    w: usize,
-    k: usize,
-    l: Vec<RenderDoc<'doc>>,
-    r: Vec<RenderDoc<'doc>>,
-) -> Vec<RenderDoc<'doc>> {
-    if fits(w, k, &l) {
-        l
-    } else {
-        r
-    }
+    Ok(())
 }
 
-fn fits<'a, 'b>(w: usize, mut k: usize, docs: &'a [RenderDoc<'b>]) -> bool {
-    for doc in docs {
-        if k > w {
-            return false;
-        } else {
-            match doc {
-                &RenderDoc::Line(_) => return true,
-                &RenderDoc::Text(s, _) => {
-                    k += s.len();
+// Checks whether everything up to the first newline of `doc` in flat mode,
+// followed by `rest`, fits in `w` columns, given that `k` are already used and
+// the indentation is `i`. `rest` is in stack order, so it is walked from the
+// back.
+//
+// Groups in `rest` are assumed to be broken. Since the first line of a broken
+// group is never longer than that of the flattened group, this gives the same
+// answer as resolving them properly, without any backtracking.
+fn fits<A>(w: usize, mut k: usize, i: usize, doc: &Ptr<DocInner<A>>, rest: &[Cmd<A>]) -> bool {
+    let mut rest = rest.iter().rev().filter_map(|cmd| match *cmd {
+        Cmd::Doc(i, mode, ref doc) => Some((i, mode, doc.clone())),
+        Cmd::Pop(_) => None,
+    });
+    let mut stack = vec![(i, Mode::Flat, doc.clone())];
+    loop {
+        let (i, mode, doc) = match stack.pop().or_else(|| rest.next()) {
+            Some(next) => next,
+            None => return true,
+        };
+        match *doc {
+            DocInner::Append(ref x, ref y) => {
+                stack.push((i, mode, y.clone()));
+                stack.push((i, mode, x.clone()));
+            }
+            DocInner::Column(ref f) => {
+                stack.push((i, mode, f.call(k).inner));
+            }
+            DocInner::FlatAlt(ref x, ref y) => {
+                let alt = if mode == Mode::Flat { y } else { x };
+                stack.push((i, mode, alt.clone()));
+            }
+            DocInner::Annotate(_, ref x)
+            | DocInner::Group(ref x)
+            | DocInner::Link(_, ref x)
+            | DocInner::Reset(ref x)
+            | DocInner::Tag(_, ref x) => {
+                stack.push((i, mode, x.clone()));
+            }
+            DocInner::Nest(j, ref x) => {
+                stack.push((nest(i, j), mode, x.clone()));
+            }
+            DocInner::Nesting(ref f) => {
+                stack.push((i, mode, f.call(i).inner));
+            }
+            DocInner::Text(ref s) => {
+                k += width(s);
+                if k > w {
+                    return false;
+                }
+            }
+            DocInner::Line(Some(s)) if mode == Mode::Flat => {
+                k += width(s);
+                if k > w {
+                    return false;
                 }
             }
+            DocInner::Line(_) => return true,
         }
     }
-    true
 }
@@ -0,0 +1,746 @@
+//! A serde `Serializer` that builds a `Doc`, so that any `Serialize` value can
+//! be pretty-printed as JSON or RON.
+//!
+//! Like the `Sparkly` impls for standard library types, sequences, maps, and
+//! structs stay on one line when they fit, and are broken with one element per
+//! line when they don't.
+
+use std::error::Error as StdError;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+use serde::ser::{
+    self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+    SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+};
+
+/// The styles are defined in the `json` module, which doesn't need the
+/// `serde` feature, so that both modules can use them. `ser::Styles` remains
+/// a name for the same type.
+pub use json::Styles;
+use Doc;
+
+/// Serializes a value as a `Doc` in the given syntax, without styles.
+pub fn to_doc<T: ?Sized + Serialize>(value: &T, syntax: Syntax) -> Result<Doc, Error> {
+    value.serialize(&Serializer::new(syntax))
+}
+
+/// The syntax a value is serialized in.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Syntax {
+    /// JSON, with the same representation of enums and options as
+    /// `serde_json`.
+    Json,
+
+    /// RON, which writes structs, tuples, enums, and options like Rust does.
+    Ron,
+}
+
+/// The error returned when a value cannot be serialized.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Error(String);
+
+impl Display for Error {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl StdError for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        Error(msg.to_string())
+    }
+}
+
+/// A serializer that builds a `Doc`.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate sparkly;
+/// # use std::collections::BTreeMap;
+/// use serde::Serialize;
+/// use sparkly::ser::{Serializer, Syntax};
+///
+/// # fn main() {
+/// let mut map = BTreeMap::new();
+/// map.insert("primes", vec![2, 3, 5, 7]);
+/// let doc = map.serialize(&Serializer::new(Syntax::Json)).unwrap();
+/// assert_eq!(doc.display_opts(80, false).to_string(), r#"{"primes": [2, 3, 5, 7]}"#);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Serializer {
+    styles: Styles,
+    syntax: Syntax,
+}
+
+impl Serializer {
+    /// Returns a serializer for the given syntax, without styles.
+    pub fn new(syntax: Syntax) -> Serializer {
+        Serializer {
+            styles: Styles::default(),
+            syntax,
+        }
+    }
+
+    /// Sets the styles applied to the output.
+    pub fn styles(self, styles: Styles) -> Serializer {
+        Serializer { styles, ..self }
+    }
+
+    fn punct(&self, s: &'static str) -> Doc {
+        Doc::from(s).style(self.styles.punctuation)
+    }
+
+    fn literal(&self, s: &'static str) -> Doc {
+        Doc::from(s).style(self.styles.literal)
+    }
+
+    fn number<T: Display>(&self, n: T) -> Doc {
+        Doc::text(n, self.styles.number)
+    }
+
+    /// Returns a float, written with as few digits as it takes to parse back
+    /// to the same value of its own type.
+    fn float<T: Debug>(&self, v: T, finite: bool) -> Doc {
+        // JSON has no representation of infinities or NaN, so, like
+        // `serde_json`, they are written as `null`.
+        if self.syntax == Syntax::Json && !finite {
+            self.literal("null")
+        } else {
+            // Unlike Display, Debug always writes a decimal point or an
+            // exponent, so floats stay floats when they are parsed back.
+            self.number(format!("{:?}", v))
+        }
+    }
+
+    fn string(&self, s: &str) -> Doc {
+        let s = match self.syntax {
+            Syntax::Json => json_string(s),
+            Syntax::Ron => format!("{:?}", s),
+        };
+        Doc::text(s, self.styles.string)
+    }
+
+    /// Returns the name of a struct or variant, or a field name in RON.
+    fn ident(&self, name: &'static str) -> Doc {
+        Doc::from(name)
+    }
+
+    /// Returns a field name, which is a string in JSON and an identifier in
+    /// RON.
+    fn field(&self, name: &'static str) -> Doc {
+        match self.syntax {
+            Syntax::Json => Doc::text(json_string(name), self.styles.key),
+            Syntax::Ron => Doc::from(name).style(self.styles.key),
+        }
+    }
+
+    fn entry(&self, key: Doc, value: Doc) -> Doc {
+        key.append(self.punct(":"))
+            .append(Doc::nbsp())
+            .append(value)
+    }
+
+    /// Lays out elements between brackets, on one line if they fit.
+    fn list(&self, open: &'static str, close: &'static str, items: Vec<Doc>) -> Doc {
+        let sep = self.punct(",").append(Doc::space());
+        self.punct(open)
+            .append(Doc::split_point().append(sep.join(items)).nest(4))
+            .append(Doc::split_point())
+            .append(self.punct(close))
+            .group()
+    }
+
+    /// Wraps a value in an object with a single key, which is how JSON
+    /// represents enum variants with data.
+    fn variant(&self, variant: &'static str, value: Doc) -> Doc {
+        self.list("{", "}", vec![self.entry(self.field(variant), value)])
+    }
+
+    fn compound(
+        &self,
+        name: Option<&'static str>,
+        open: &'static str,
+        close: &'static str,
+        len: Option<usize>,
+    ) -> Compound<'_> {
+        Compound {
+            close,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+            name,
+            open,
+            ser: self,
+            tuple: false,
+        }
+    }
+}
+
+/// Escapes and quotes a string as JSON.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            _ if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl<'a> ser::Serializer for &'a Serializer {
+    type Ok = Doc;
+    type Error = Error;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Doc, Error> {
+        Ok(self.literal(if v { "true" } else { "false" }))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Doc, Error> {
+        Ok(self.number(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Doc, Error> {
+        Ok(self.float(v, v.is_finite()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Doc, Error> {
+        Ok(self.float(v, v.is_finite()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Doc, Error> {
+        match self.syntax {
+            Syntax::Json => self.serialize_str(v.encode_utf8(&mut [0; 4])),
+            Syntax::Ron => Ok(Doc::text(format!("{:?}", v), self.styles.string)),
+        }
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Doc, Error> {
+        Ok(self.string(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Doc, Error> {
+        let mut seq = self.serialize_seq(Some(v.len()))?;
+        for b in v {
+            SerializeSeq::serialize_element(&mut seq, b)?;
+        }
+        SerializeSeq::end(seq)
+    }
+
+    fn serialize_none(self) -> Result<Doc, Error> {
+        Ok(match self.syntax {
+            Syntax::Json => self.literal("null"),
+            Syntax::Ron => self.literal("None"),
+        })
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Doc, Error> {
+        let doc = value.serialize(self)?;
+        Ok(match self.syntax {
+            Syntax::Json => doc,
+            Syntax::Ron => self.literal("Some").append(self.list("(", ")", vec![doc])),
+        })
+    }
+
+    fn serialize_unit(self) -> Result<Doc, Error> {
+        Ok(match self.syntax {
+            Syntax::Json => self.literal("null"),
+            Syntax::Ron => self.literal("()"),
+        })
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Doc, Error> {
+        match self.syntax {
+            Syntax::Json => self.serialize_unit(),
+            Syntax::Ron => Ok(self.ident(name)),
+        }
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Doc, Error> {
+        match self.syntax {
+            Syntax::Json => self.serialize_str(variant),
+            Syntax::Ron => Ok(self.ident(variant)),
+        }
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Doc, Error> {
+        let doc = value.serialize(self)?;
+        Ok(match self.syntax {
+            Syntax::Json => doc,
+            Syntax::Ron => self.ident(name).append(self.list("(", ")", vec![doc])),
+        })
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Doc, Error> {
+        let doc = value.serialize(self)?;
+        Ok(match self.syntax {
+            Syntax::Json => self.variant(variant, doc),
+            Syntax::Ron => self.ident(variant).append(self.list("(", ")", vec![doc])),
+        })
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(None, "[", "]", len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, Error> {
+        Ok(match self.syntax {
+            Syntax::Json => self.compound(None, "[", "]", Some(len)),
+            Syntax::Ron => Compound {
+                tuple: true,
+                ..self.compound(None, "(", ")", Some(len))
+            },
+        })
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Compound<'a>, Error> {
+        Ok(match self.syntax {
+            Syntax::Json => self.compound(None, "[", "]", Some(len)),
+            Syntax::Ron => self.compound(Some(name), "(", ")", Some(len)),
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        Ok(match self.syntax {
+            Syntax::Json => self.compound(Some(variant), "[", "]", Some(len)),
+            Syntax::Ron => self.compound(Some(variant), "(", ")", Some(len)),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(None, "{", "}", len))
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Compound<'a>, Error> {
+        Ok(match self.syntax {
+            Syntax::Json => self.compound(None, "{", "}", Some(len)),
+            Syntax::Ron => self.compound(Some(name), "(", ")", Some(len)),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        Ok(match self.syntax {
+            Syntax::Json => self.compound(Some(variant), "{", "}", Some(len)),
+            Syntax::Ron => self.compound(Some(variant), "(", ")", Some(len)),
+        })
+    }
+}
+
+/// The serializer for sequences, tuples, maps, structs, and the variants
+/// containing them.
+pub struct Compound<'a> {
+    close: &'static str,
+    items: Vec<Doc>,
+    key: Option<Doc>,
+    /// The name of the struct or variant. In JSON, only variants are named,
+    /// and the elements are wrapped in an object with the name as its key.
+    name: Option<&'static str>,
+    open: &'static str,
+    ser: &'a Serializer,
+    /// Whether this is an unnamed tuple in RON, which needs a trailing comma
+    /// if it has one element.
+    tuple: bool,
+}
+
+impl<'a> Compound<'a> {
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let doc = value.serialize(self.ser)?;
+        self.items.push(doc);
+        Ok(())
+    }
+
+    fn field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        let doc = value.serialize(self.ser)?;
+        self.items.push(self.ser.entry(self.ser.field(key), doc));
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Doc, Error> {
+        if self.tuple && self.items.len() == 1 {
+            let item = self.items.pop().unwrap();
+            self.items.push(item.append(self.ser.punct(",")));
+        }
+        let list = self.ser.list(self.open, self.close, self.items);
+        Ok(match (self.name, self.ser.syntax) {
+            (None, _) => list,
+            (Some(name), Syntax::Json) => self.ser.variant(name, list),
+            (Some(name), Syntax::Ron) => self.ser.ident(name).append(list),
+        })
+    }
+}
+
+impl<'a> SerializeSeq for Compound<'a> {
+    type Ok = Doc;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Doc, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> SerializeTuple for Compound<'a> {
+    type Ok = Doc;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Doc, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> SerializeTupleStruct for Compound<'a> {
+    type Ok = Doc;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Doc, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> SerializeTupleVariant for Compound<'a> {
+    type Ok = Doc;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Doc, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> SerializeMap for Compound<'a> {
+    type Ok = Doc;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(match self.ser.syntax {
+            Syntax::Json => key.serialize(KeySerializer(self.ser))?,
+            Syntax::Ron => key.serialize(self.ser)?,
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error("serialize_value called before serialize_key".to_string()))?;
+        let doc = value.serialize(self.ser)?;
+        self.items.push(self.ser.entry(key, doc));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Doc, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> SerializeStruct for Compound<'a> {
+    type Ok = Doc;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Doc, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> SerializeStructVariant for Compound<'a> {
+    type Ok = Doc;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Doc, Error> {
+        self.finish()
+    }
+}
+
+/// Serializes JSON object keys, which must be strings. Like `serde_json`,
+/// numbers, booleans, and unit variants are turned into strings; anything
+/// else is an error.
+struct KeySerializer<'a>(&'a Serializer);
+
+impl<'a> KeySerializer<'a> {
+    fn key<T: Display>(&self, key: T) -> Result<Doc, Error> {
+        Ok(Doc::text(json_string(&key.to_string()), self.0.styles.key))
+    }
+}
+
+fn key_error() -> Error {
+    Error("JSON object keys must be strings".to_string())
+}
+
+impl<'a> ser::Serializer for KeySerializer<'a> {
+    type Ok = Doc;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<Doc, Error>;
+    type SerializeTuple = ser::Impossible<Doc, Error>;
+    type SerializeTupleStruct = ser::Impossible<Doc, Error>;
+    type SerializeTupleVariant = ser::Impossible<Doc, Error>;
+    type SerializeMap = ser::Impossible<Doc, Error>;
+    type SerializeStruct = ser::Impossible<Doc, Error>;
+    type SerializeStructVariant = ser::Impossible<Doc, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Doc, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Doc, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_char(self, v: char) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Doc, Error> {
+        self.key(v)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Doc, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_none(self) -> Result<Doc, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<Doc, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit(self) -> Result<Doc, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Doc, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Doc, Error> {
+        self.key(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Doc, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Doc, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(key_error())
+    }
+}
@@ -0,0 +1,384 @@
+//! Parsing and pretty-printing S-expressions in the style of Lisp code.
+//!
+//! Lists that fit on the rest of the line are kept on it. Otherwise, the
+//! arguments of a call are aligned under its first argument, and closing
+//! parentheses are stacked at the end of the last line rather than put on
+//! lines of their own:
+//!
+//! ```text
+//! (foo (bar baz)
+//!      (quux spam
+//!            eggs))
+//! ```
+//!
+//! Special forms like `defun`, `let`, and `lambda` are laid out by "body
+//! rules": their first few arguments stay on the line of the head, and the
+//! rest (the body) are indented by two columns past the opening parenthesis.
+//! Lists whose first element isn't a symbol, such as `let` bindings, are
+//! aligned under that element.
+//!
+//! ```rust
+//! use sparkly::sexpr::{self, Options};
+//! use sparkly::{Doc, RenderOptions};
+//!
+//! let expr = sexpr::parse("(define (square x) (* x x))").unwrap();
+//! let doc: Doc<()> = expr.to_doc_with(&Options::new().rule("define", 1));
+//! let mut out = String::new();
+//! doc.render_fmt(&mut out, RenderOptions::new(20)).unwrap();
+//! assert_eq!(out, "(define (square x)\n  (* x x))");
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::iter::once;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+use {Doc, MaybeSync, RenderOptions, Sparkly};
+
+/// An S-expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SExpr {
+    /// A symbol, number, string, or any other atom, as it was written.
+    /// Strings include their quotes, and their escapes are left as they are.
+    Atom(String),
+
+    /// A parenthesized list.
+    List(Vec<SExpr>),
+
+    /// An expression with a reader prefix: `'`, `` ` ``, `,`, `,@`, or the `#`
+    /// of a vector.
+    Prefixed(&'static str, Box<SExpr>),
+}
+
+impl SExpr {
+    /// Returns a `Doc` for the expression, laid out with the given options.
+    ///
+    /// The expression is laid out recursively, so one nested far more deeply
+    /// than `parse` allows may overflow the stack.
+    pub fn to_doc_with<A: 'static + MaybeSync>(&self, opts: &Options) -> Doc<A> {
+        layout(self, opts)
+    }
+}
+
+impl<A: 'static + MaybeSync> Sparkly<A> for SExpr {
+    fn to_doc(&self) -> Doc<A> {
+        self.to_doc_with(&Options::new())
+    }
+}
+
+impl Display for SExpr {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        self.to_doc_with::<()>(&Options::new())
+            .render_fmt(fmt, RenderOptions::new(80))
+    }
+}
+
+/// Returns a `Doc` for an expression.
+fn layout<A: 'static + MaybeSync>(expr: &SExpr, opts: &Options) -> Doc<A> {
+    match *expr {
+        // The lines of a multi-line string must be written as they are.
+        SExpr::Atom(ref s) if s.contains('\n') => Doc::line()
+            .join(s.split('\n').map(|line| Doc::from(line.to_string())))
+            .dedent(),
+        SExpr::Atom(ref s) => Doc::from(s.to_string()),
+        SExpr::List(ref items) => list(items, opts),
+        SExpr::Prefixed(prefix, ref expr) => Doc::from(prefix).append(layout(expr, opts)),
+    }
+}
+
+fn list<A: 'static + MaybeSync>(items: &[SExpr], opts: &Options) -> Doc<A> {
+    let mut docs = items.iter().map(|item| layout(item, opts));
+    let head_doc = match docs.next() {
+        Some(doc) => doc,
+        None => return Doc::from("()"),
+    };
+
+    let doc = match items[0] {
+        _ if items.len() == 1 => Doc::from("(").append(head_doc).append(")".into()),
+        SExpr::Atom(ref s) if is_symbol(s) => match opts.rules.get(s) {
+            Some(&n) => {
+                let mut doc = Doc::from("(").append(head_doc);
+                if n > 0 {
+                    let distinguished = Doc::space().join(docs.by_ref().take(n)).align();
+                    doc = doc.append(Doc::nbsp()).append(distinguished.group());
+                }
+                for body in docs {
+                    doc = doc.append(Doc::space()).append(body);
+                }
+                doc.append(")".into()).hang(opts.body_indent)
+            }
+            None => Doc::from("(")
+                .append(head_doc)
+                .append(Doc::nbsp())
+                .append(Doc::space().join(docs).align())
+                .append(")".into()),
+        },
+        _ => Doc::from("(")
+            .append(Doc::space().join(once(head_doc).chain(docs)).align())
+            .append(")".into()),
+    };
+    doc.group()
+}
+
+/// Returns whether an atom is a symbol, rather than a number, string, or
+/// `#` literal.
+fn is_symbol(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some('"') | Some('#') | None => false,
+        Some('+') | Some('-') | Some('.') => !chars.next().is_some_and(|c| c.is_ascii_digit()),
+        Some(c) => !c.is_ascii_digit(),
+    }
+}
+
+/// Options controlling how S-expressions are laid out.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    body_indent: usize,
+    rules: BTreeMap<String, usize>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options::new()
+    }
+}
+
+impl Options {
+    /// Returns the default options: a body indent of two columns, and the
+    /// rules `defun` (2), `lambda` (1), and `let` (1).
+    pub fn new() -> Options {
+        Options::without_rules()
+            .rule("defun", 2)
+            .rule("lambda", 1)
+            .rule("let", 1)
+    }
+
+    /// Returns the default options, but with no rules, so that every list is
+    /// laid out like a call.
+    pub fn without_rules() -> Options {
+        Options {
+            body_indent: 2,
+            rules: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the number of columns past the opening parenthesis that the body
+    /// of a special form is indented by.
+    pub fn body_indent(self, body_indent: usize) -> Options {
+        Options {
+            body_indent,
+            ..self
+        }
+    }
+
+    /// Adds a rule for lists whose head is the symbol `head`: the first `args`
+    /// arguments are kept on the line of the head (and aligned with each other
+    /// if they don't fit), and the rest are the body. A rule for `head` that
+    /// was already added is replaced.
+    pub fn rule(mut self, head: &str, args: usize) -> Options {
+        self.rules.insert(head.to_string(), args);
+        self
+    }
+}
+
+/// The number of lists and prefixes that parsed text can be nested in. Deeper
+/// text is rejected rather than risk overflowing the stack, either while
+/// parsing it or while laying out the result. `SExpr`s built in code are not
+/// checked.
+const MAX_DEPTH: usize = 128;
+
+/// Parses text that contains exactly one S-expression, optionally surrounded
+/// by whitespace and comments. Lists and prefixes may be nested up to 128
+/// deep.
+pub fn parse(text: &str) -> Result<SExpr, ParseError> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        depth: 0,
+    };
+    let expr = parser.expr()?;
+    parser.skip_whitespace();
+    if parser.pos == text.len() {
+        Ok(expr)
+    } else {
+        Err(parser.error("trailing characters"))
+    }
+}
+
+/// Parses text that contains any number of S-expressions, such as a source
+/// file. Comments are skipped, so they are not kept in the output.
+pub fn parse_all(text: &str) -> Result<Vec<SExpr>, ParseError> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        depth: 0,
+    };
+    let mut exprs = Vec::new();
+    parser.skip_whitespace();
+    while parser.pos < text.len() {
+        exprs.push(parser.expr()?);
+        parser.skip_whitespace();
+    }
+    Ok(exprs)
+}
+
+/// The error returned for text that isn't a valid S-expression.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    /// The byte offset in the text at which the error was found.
+    pub offset: usize,
+
+    msg: &'static str,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "{} at byte {}", self.msg, self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl StdError for ParseError {}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &'static str) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            msg,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).cloned()
+    }
+
+    /// Skips whitespace and `;` comments.
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(b';') => {
+                    while let Some(b) = self.peek() {
+                        if b == b'\n' {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Result<SExpr, ParseError> {
+        self.skip_whitespace();
+        let rest = &self.text[self.pos..];
+        let prefix = if rest.starts_with(",@") {
+            Some(",@")
+        } else if rest.starts_with("#(") {
+            Some("#")
+        } else {
+            ["'", "`", ","]
+                .iter()
+                .cloned()
+                .find(|p| rest.starts_with(p))
+        };
+        if let Some(prefix) = prefix {
+            return self.nested(|parser| {
+                parser.pos += prefix.len();
+                Ok(SExpr::Prefixed(prefix, Box::new(parser.expr()?)))
+            });
+        }
+
+        match self.peek() {
+            Some(b'(') => self.nested(Parser::list),
+            Some(b')') => Err(self.error("unexpected ')'")),
+            Some(b'"') => self.string(),
+            Some(_) => Ok(self.atom()),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    /// Parses a list or prefixed expression with `f`, unless it would be
+    /// nested too deeply.
+    fn nested<F>(&mut self, f: F) -> Result<SExpr, ParseError>
+    where
+        F: FnOnce(&mut Parser<'a>) -> Result<SExpr, ParseError>,
+    {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("recursion limit exceeded"));
+        }
+        self.depth += 1;
+        let expr = f(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn list(&mut self) -> Result<SExpr, ParseError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b')') => {
+                    self.pos += 1;
+                    return Ok(SExpr::List(items));
+                }
+                Some(_) => items.push(self.expr()?),
+                None => return Err(self.error("expected ')'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<SExpr, ParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(SExpr::Atom(self.text[start..self.pos].to_string()));
+                }
+                Some(b'\\') => self.pos += 2,
+                // Multi-byte characters are skipped a byte at a time; none of
+                // their bytes are quotes or backslashes.
+                Some(_) => self.pos += 1,
+                None => break,
+            }
+        }
+        self.pos = self.text.len();
+        Err(self.error("unterminated string"))
+    }
+
+    fn atom(&mut self) -> SExpr {
+        let start = self.pos;
+        if self.text[self.pos..].starts_with("#\\") {
+            // A character literal, like `#\(` or `#\ `, whose character is
+            // part of the atom even if it is a delimiter.
+            self.pos += 2;
+            self.pos += self.text[self.pos..]
+                .chars()
+                .next()
+                .map_or(0, char::len_utf8);
+        }
+        while let Some(b) = self.peek() {
+            match b {
+                b'(' | b')' | b'"' | b';' => break,
+                b if b.is_ascii_whitespace() => break,
+                _ => self.pos += 1,
+            }
+        }
+        SExpr::Atom(self.text[start..self.pos].to_string())
+    }
+}
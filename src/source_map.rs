@@ -0,0 +1,143 @@
+//! Finding where tagged parts of a `Doc` end up in its rendered text.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use render::{render, spaces, Never, Sink};
+use width::width;
+use {Doc, RenderOptions};
+
+/// A position in rendered text. Both the line and the column start at zero,
+/// and the column is measured in the same units as the page width.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Position {
+    /// The line the position is on.
+    pub line: usize,
+
+    /// The column the position is at.
+    pub column: usize,
+}
+
+/// The part of the rendered text that a tagged `Doc` occupies.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Region {
+    /// The ID the `Doc` was tagged with.
+    pub id: usize,
+
+    /// The byte range of the region in the text.
+    pub bytes: Range<usize>,
+
+    /// The position the region starts at.
+    pub start: Position,
+
+    /// The position just past the end of the region.
+    pub end: Position,
+}
+
+/// Rendered text, along with where each tagged `Doc` ended up in it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceMap {
+    /// The rendered text, without any styling.
+    pub text: String,
+
+    /// The regions of the tagged `Doc`s, in the order they start in. When
+    /// regions are nested, the outer one comes first. A `Doc` that appears
+    /// several times in the output has several regions.
+    pub regions: Vec<Region>,
+}
+
+impl SourceMap {
+    /// Returns the regions containing the given byte offset, from the
+    /// outermost to the innermost.
+    pub fn regions_at(&self, offset: usize) -> impl Iterator<Item = &Region> {
+        self.regions
+            .iter()
+            .filter(move |r| r.bytes.start <= offset && offset < r.bytes.end)
+    }
+}
+
+impl<A> Doc<A> {
+    /// Renders the `Doc` as text with the given options, recording where each
+    /// part of it that was tagged with `Doc::tagged` ended up. Annotations are
+    /// ignored.
+    pub fn source_map(&self, opts: RenderOptions) -> SourceMap {
+        let mut sink = SourceMapSink {
+            column: 0,
+            line: 0,
+            map: SourceMap::default(),
+            open: Vec::new(),
+        };
+        match render(&opts, &self.inner, &mut sink) {
+            Ok(()) => sink.map,
+            Err(never) => match never {},
+        }
+    }
+}
+
+/// A `Sink` that builds a `SourceMap`.
+struct SourceMapSink {
+    column: usize,
+    line: usize,
+    map: SourceMap,
+
+    /// The indices in `map.regions` of the regions that have been started but
+    /// not ended.
+    open: Vec<usize>,
+}
+
+impl SourceMapSink {
+    fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+impl<A> Sink<A> for SourceMapSink {
+    type Error = Never;
+
+    fn text(&mut self, s: &str) -> Result<(), Never> {
+        self.column += width(s);
+        self.map.text.push_str(s);
+        Ok(())
+    }
+
+    fn line(&mut self, indent: usize) -> Result<(), Never> {
+        self.line += 1;
+        self.column = indent;
+        self.map.text.push('\n');
+        self.map.text.extend(spaces(indent));
+        Ok(())
+    }
+
+    fn push_annotation(&mut self, _: &A) -> Result<(), Never> {
+        Ok(())
+    }
+
+    fn pop_annotation(&mut self) -> Result<(), Never> {
+        Ok(())
+    }
+
+    fn push_tag(&mut self, id: usize) -> Result<(), Never> {
+        let offset = self.map.text.len();
+        self.open.push(self.map.regions.len());
+        self.map.regions.push(Region {
+            id,
+            bytes: offset..offset,
+            start: self.position(),
+            end: self.position(),
+        });
+        Ok(())
+    }
+
+    fn pop_tag(&mut self) -> Result<(), Never> {
+        let i = self.open.pop().expect("tags are properly nested");
+        let end = self.position();
+        let region = &mut self.map.regions[i];
+        region.bytes.end = self.map.text.len();
+        region.end = end;
+        Ok(())
+    }
+}
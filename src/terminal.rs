@@ -0,0 +1,164 @@
+//! Detecting the width and colour support of the terminal output is going to.
+
+use std::collections::HashMap;
+use std::env::var;
+#[cfg(not(unix))]
+use std::io::IsTerminal;
+use std::io::{stderr, stdout};
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use color::ColorLevel;
+use RenderOptions;
+
+/// The environment variables that affect detection.
+const VARS: [&str; 6] = [
+    "CLICOLOR",
+    "CLICOLOR_FORCE",
+    "COLORTERM",
+    "COLUMNS",
+    "NO_COLOR",
+    "TERM",
+];
+
+/// The width used when no other width can be found.
+const DEFAULT_WIDTH: usize = 80;
+
+/// What is known about the place output is going to, from which the width and
+/// colour support to render with are worked out.
+///
+/// The fields are public so that an environment can be constructed by hand,
+/// for example in tests.
+///
+/// The width is found from, in order of precedence:
+///
+///  1. The `COLUMNS` environment variable, if it is a positive integer.
+///  2. The size of the terminal, if output is going to one.
+///  3. A default of 80 columns.
+///
+/// Whether colour is used is decided by, in order of precedence:
+///
+///  1. If `NO_COLOR` is set to anything but the empty string, no colour is
+///     used.
+///  2. If `CLICOLOR_FORCE` is set to anything but the empty string or `0`,
+///     colour is used, even if output is not going to a terminal.
+///  3. If output is not going to a terminal, `TERM` is `dumb`, or `CLICOLOR`
+///     is `0`, no colour is used.
+///  4. Otherwise, colour is used.
+///
+/// When colour is used, all RGB colours are allowed if `COLORTERM` is
+/// `truecolor` or `24bit`, the 256-colour palette is allowed if `TERM` ends
+/// in `256color`, and only the basic colours are allowed otherwise.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Terminal {
+    /// The environment variables that affect detection. Other variables may
+    /// be present, but are ignored.
+    pub vars: HashMap<String, String>,
+
+    /// Whether output is going to a terminal.
+    pub is_tty: bool,
+
+    /// The width of the terminal, if output is going to one and its width is
+    /// known.
+    pub size: Option<usize>,
+}
+
+impl Terminal {
+    /// Detects the terminal for the given file descriptor.
+    #[cfg(unix)]
+    pub fn for_fd<F: AsRawFd + ?Sized>(fd: &F) -> Terminal {
+        let fd = fd.as_raw_fd();
+        let is_tty = is_tty(fd);
+        Terminal {
+            vars: vars(),
+            is_tty,
+            size: if is_tty { fd_width(fd) } else { None },
+        }
+    }
+
+    /// Detects the terminal for standard output.
+    pub fn stdout() -> Terminal {
+        #[cfg(unix)]
+        return Terminal::for_fd(&stdout());
+        #[cfg(not(unix))]
+        return Terminal {
+            vars: vars(),
+            is_tty: stdout().is_terminal(),
+            size: None,
+        };
+    }
+
+    /// Detects the terminal for standard error.
+    pub fn stderr() -> Terminal {
+        #[cfg(unix)]
+        return Terminal::for_fd(&stderr());
+        #[cfg(not(unix))]
+        return Terminal {
+            vars: vars(),
+            is_tty: stderr().is_terminal(),
+            size: None,
+        };
+    }
+
+    /// Returns the width to render with.
+    pub fn width(&self) -> usize {
+        self.var("COLUMNS")
+            .and_then(|s| s.trim().parse().ok())
+            .filter(|&w| w > 0)
+            .or(self.size)
+            .unwrap_or(DEFAULT_WIDTH)
+    }
+
+    /// Returns the colours that may be used.
+    pub fn color(&self) -> ColorLevel {
+        let force = self.var("CLICOLOR_FORCE").is_some_and(|s| s != "0");
+        let disabled =
+            !self.is_tty || self.var("TERM") == Some("dumb") || self.var("CLICOLOR") == Some("0");
+        if self.var("NO_COLOR").is_some() || (disabled && !force) {
+            return ColorLevel::None;
+        }
+
+        match (self.var("COLORTERM"), self.var("TERM")) {
+            (Some("truecolor"), _) | (Some("24bit"), _) => ColorLevel::TrueColor,
+            (_, Some(term)) if term.ends_with("256color") => ColorLevel::Ansi256,
+            _ => ColorLevel::Ansi16,
+        }
+    }
+
+    /// Returns the options to render with, which are the detected width and
+    /// colour support, and otherwise the defaults.
+    pub fn options(&self) -> RenderOptions {
+        RenderOptions::new(self.width()).color(self.color())
+    }
+
+    /// Returns the value of an environment variable, treating empty values as
+    /// unset.
+    fn var(&self, name: &str) -> Option<&str> {
+        self.vars
+            .get(name)
+            .map(|s| &s[..])
+            .filter(|s| !s.is_empty())
+    }
+}
+
+/// Reads the environment variables that affect detection.
+fn vars() -> HashMap<String, String> {
+    VARS.iter()
+        .filter_map(|&name| var(name).ok().map(|value| (name.to_string(), value)))
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_tty(fd: RawFd) -> bool {
+    unsafe { ::libc::isatty(fd) == 1 }
+}
+
+#[cfg(unix)]
+fn fd_width(fd: RawFd) -> Option<usize> {
+    let mut size: ::libc::winsize = unsafe { ::std::mem::zeroed() };
+    if unsafe { ::libc::ioctl(fd, ::libc::TIOCGWINSZ, &mut size) } == 0 && size.ws_col > 0 {
+        Some(size.ws_col as usize)
+    } else {
+        None
+    }
+}
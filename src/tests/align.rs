@@ -0,0 +1,101 @@
+use tests::words;
+use {Doc, LayoutMode, RenderOptions, Sparkly};
+
+/// Lays out a call Lisp-style, with the arguments lined up under the first.
+fn call(f: &'static str, args: Vec<Doc>) -> Doc {
+    Doc::from("(")
+        .append(Doc::from(f))
+        .append(Doc::from(" "))
+        .append(Doc::space().join(args).align())
+        .append(Doc::from(")"))
+        .group()
+}
+
+tests! {
+    [align, 80, false]
+        Doc::from("(foo ")
+            .append(Doc::line().join(words(vec!["bar", "baz"])).align())
+            .append(Doc::from(")")) =>
+        "(foo bar\n     baz)",
+    [align_under_nest, 80, false]
+        Doc::from("a")
+            .append(Doc::from("b").append(Doc::line()).append(Doc::from("c")).align())
+            .nest(4) =>
+        "ab\n c",
+    [hang, 10, false]
+        Doc::from("let ")
+            .append(Doc::space().join(words(vec!["x", "=", "foo", "bar"])).hang(2).group()) =>
+        "let x\n      =\n      foo\n      bar",
+    [indent, 80, false]
+        Doc::from("x")
+            .append(Doc::line())
+            .append(Doc::from("a").append(Doc::line()).append(Doc::from("b")).indent(4)) =>
+        "x\n    a\n    b",
+    [dedent, 80, false]
+        Doc::from("x")
+            .append(Doc::line())
+            .append(Doc::from("a").append(Doc::line()).append(Doc::from("b")).dedent())
+            .append(Doc::line())
+            .append(Doc::from("c"))
+            .nest(4) =>
+        "x\n    a\nb\n    c",
+    [dedent_under_align, 80, false]
+        Doc::from("(foo ")
+            .append(Doc::from("\"a").append(Doc::line()).append(Doc::from("b\"")).dedent().align()) =>
+        "(foo \"a\nb\"",
+    [align_in_flat_group, 80, false]
+        Doc::from("foo ").append(Doc::lines(words(vec!["a", "b"])).align()).group() =>
+        "foo a\n    b",
+    [indent_in_flat_group, 80, false]
+        Doc::from("x")
+            .append(Doc::space())
+            .append(Doc::lines(words(vec!["a", "b"])).indent(2))
+            .group() =>
+        "x   a\n    b",
+    [column, 80, false]
+        Doc::from("ab").append(Doc::column(|k| Doc::from(k.to_string()))) =>
+        "ab2",
+    [nesting, 80, false]
+        Doc::line().append(Doc::nesting(|i| Doc::from(i.to_string()))).nest(3) =>
+        "\n   3",
+    [lisp_flat, 80, false]
+        call("defun", vec![
+            Doc::from("foo"),
+            call("x", words(vec!["y"])),
+            call("bar", words(vec!["x", "y"])),
+        ]) =>
+        "(defun foo (x y) (bar x y))",
+    [lisp_hanging, 20, false]
+        call("defun", vec![
+            Doc::from("foo"),
+            call("list", words(vec!["x", "y"])),
+            call("bar", words(vec!["x", "yyyyyyyy"])),
+        ]) =>
+        "(defun foo\n       (list x y)\n       (bar x\n            yyyyyyyy))"
+}
+
+#[test]
+fn optimal_align() {
+    let doc = call(
+        "defun",
+        vec![
+            Doc::from("foo"),
+            call("list", words(vec!["x", "y"])),
+            call("bar", words(vec!["x", "yyyyyyyy"])),
+        ],
+    );
+    let opts = RenderOptions::new(20).mode(LayoutMode::Optimal);
+    assert_eq!(
+        doc.to_doc().display_with(opts).to_string(),
+        "(defun foo\n       (list x y)\n       (bar x\n            yyyyyyyy))"
+    );
+}
+
+#[test]
+fn optimal_align_in_flat_group() {
+    let doc = Doc::from("foo ")
+        .append(Doc::lines(words(vec!["a", "b"])).align())
+        .group();
+    let opts = RenderOptions::new(80).mode(LayoutMode::Optimal);
+    assert_eq!(doc.display_with(opts).to_string(), "foo a\n    b");
+}
@@ -0,0 +1,146 @@
+use {Colour, Doc, RenderOptions, Sink, Style};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Keyword,
+    Identifier,
+    Literal,
+}
+
+fn style(tok: &Token) -> Style {
+    match *tok {
+        Token::Keyword => Colour::Purple.bold(),
+        Token::Identifier => Colour::Blue.normal(),
+        Token::Literal => Colour::Green.normal(),
+    }
+}
+
+fn tok(s: &'static str, tok: Token) -> Doc<Token> {
+    Doc::from(s).annotate(tok)
+}
+
+fn binding() -> Doc<Token> {
+    tok("let", Token::Keyword)
+        .append(Doc::nbsp())
+        .append(tok("x", Token::Identifier))
+        .append(Doc::from(" ="))
+        .append(Doc::space().append(tok("42", Token::Literal)).nest(2))
+        .group()
+}
+
+/// Records the annotated regions a `Doc` is rendered with.
+#[derive(Default)]
+struct Regions {
+    open: Vec<Token>,
+    out: Vec<(Vec<Token>, String)>,
+}
+
+impl Sink<Token> for Regions {
+    type Error = ();
+
+    fn text(&mut self, s: &str) -> Result<(), ()> {
+        self.out.push((self.open.clone(), s.to_string()));
+        Ok(())
+    }
+
+    fn line(&mut self, _: usize) -> Result<(), ()> {
+        Ok(())
+    }
+
+    fn push_annotation(&mut self, tok: &Token) -> Result<(), ()> {
+        self.open.push(*tok);
+        Ok(())
+    }
+
+    fn pop_annotation(&mut self) -> Result<(), ()> {
+        self.open.pop().map(|_| ()).ok_or(())
+    }
+}
+
+#[test]
+fn map_annotations_preserves_layout() {
+    let doc = binding();
+    for &w in &[80, 5] {
+        let plain = doc.map_annotations(|_| Style::new());
+        let styled = doc.map_annotations(style);
+        assert_eq!(
+            plain.display_opts(w, false).to_string(),
+            styled.display_opts(w, false).to_string()
+        );
+    }
+    assert_eq!(
+        doc.map_annotations(style)
+            .display_opts(5, false)
+            .to_string(),
+        "let x =\n  42"
+    );
+}
+
+#[test]
+fn map_annotations_to_style() {
+    let doc = binding().map_annotations(style);
+    assert_eq!(
+        doc.display_opts(80, true).to_string(),
+        format!(
+            "{} {} = {}",
+            Colour::Purple.bold().paint("let"),
+            Colour::Blue.paint("x"),
+            Colour::Green.paint("42")
+        )
+    );
+}
+
+#[test]
+fn map_annotations_through_column() {
+    let doc = tok("f", Token::Identifier)
+        .append(Doc::from("("))
+        .append(
+            Doc::line()
+                .join(vec![tok("1", Token::Literal), tok("2", Token::Literal)])
+                .align(),
+        )
+        .append(Doc::from(")"));
+    assert_eq!(
+        doc.map_annotations(style)
+            .display_opts(80, false)
+            .to_string(),
+        "f(1\n  2)"
+    );
+}
+
+#[test]
+fn sink_sees_nested_regions() {
+    let doc = binding().annotate(Token::Keyword);
+    let mut sink = Regions::default();
+    doc.render_sink(RenderOptions::new(80), &mut sink).unwrap();
+    assert!(sink.open.is_empty());
+    assert_eq!(
+        sink.out,
+        vec![
+            (vec![Token::Keyword, Token::Keyword], "let".to_string()),
+            (vec![Token::Keyword], " ".to_string()),
+            (vec![Token::Keyword, Token::Identifier], "x".to_string()),
+            (vec![Token::Keyword], " =".to_string()),
+            (vec![Token::Keyword], " ".to_string()),
+            (vec![Token::Keyword, Token::Literal], "42".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn style_still_works() {
+    let doc = Doc::text("hi", Colour::Red.normal()).append(Doc::from("!"));
+    assert_eq!(
+        doc.display_opts(80, true).to_string(),
+        format!("{}!", Colour::Red.paint("hi"))
+    );
+}
+
+#[test]
+fn render_fmt_ignores_annotations() {
+    let mut out = String::new();
+    binding()
+        .render_fmt(&mut out, RenderOptions::new(8))
+        .unwrap();
+    assert_eq!(out, "let x =\n  42");
+}
@@ -0,0 +1,81 @@
+use {Colour, Doc, Style};
+
+fn render(doc: &Doc) -> String {
+    doc.display_opts(80, true).to_string()
+}
+
+#[test]
+fn runs_are_coalesced() {
+    let red = Colour::Red.normal();
+    let doc = Doc::text("a", red)
+        .append(Doc::text("b", red))
+        .append(Doc::from("c").append(Doc::from("d")).style(red));
+    assert_eq!(
+        render(&doc),
+        format!("{}abcd{}", red.prefix(), red.suffix())
+    );
+}
+
+#[test]
+fn additions_do_not_reset() {
+    let doc = Doc::from("a")
+        .append(Doc::text("b", Style::new().bold()))
+        .style(Colour::Red.normal());
+    assert_eq!(
+        render(&doc),
+        format!(
+            "{}a{}b{}",
+            Colour::Red.prefix(),
+            Style::new().bold().prefix(),
+            Colour::Red.suffix()
+        )
+    );
+}
+
+#[test]
+fn removals_reset() {
+    let doc = Doc::text("a", Colour::Red.bold()).append(Doc::text("b", Colour::Red.normal()));
+    assert_eq!(
+        render(&doc),
+        format!(
+            "{}a\x1b[0m{}b\x1b[0m",
+            Colour::Red.bold().prefix(),
+            Colour::Red.prefix()
+        )
+    );
+}
+
+#[test]
+fn indentation_is_not_styled() {
+    let doc = Doc::from("a")
+        .append(Doc::line())
+        .append(Doc::from("b"))
+        .nest(2)
+        .style(Colour::Red.normal());
+    assert_eq!(
+        render(&doc),
+        format!(
+            "{p}a{s}\n  {p}b{s}",
+            p = Colour::Red.prefix(),
+            s = Colour::Red.suffix()
+        )
+    );
+}
+
+#[test]
+fn unstyled_text_has_no_escapes() {
+    let doc = Doc::from("a").append(Doc::text("b", Style::new()));
+    assert_eq!(render(&doc), "ab");
+}
+
+#[test]
+fn io_matches_fmt() {
+    let doc = Doc::from("a")
+        .append(Doc::text("b", Colour::Blue.underline()))
+        .append(Doc::line())
+        .append(Doc::from("c"))
+        .style(Colour::Red.normal());
+    let mut buf = Vec::new();
+    doc.render_to(&mut buf, 80, true).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), render(&doc));
+}
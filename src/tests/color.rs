@@ -0,0 +1,97 @@
+use {ColorLevel, Colour, Doc, RenderOptions, Style};
+
+fn doc() -> Doc {
+    Doc::text(
+        "x",
+        Colour::RGB(0xd7, 0x00, 0x00).on(Colour::Fixed(250)).bold(),
+    )
+}
+
+#[test]
+fn truecolor_is_verbatim() {
+    let sty = Colour::RGB(1, 2, 3).on(Colour::Fixed(17));
+    assert_eq!(ColorLevel::TrueColor.downsample(sty), sty);
+    assert_eq!(
+        doc().display_opts(80, ColorLevel::TrueColor).to_string(),
+        doc().display_opts(80, true).to_string()
+    );
+}
+
+#[test]
+fn ansi256() {
+    let sty = ColorLevel::Ansi256.downsample(Colour::RGB(0xd7, 0x00, 0x00).normal());
+    assert_eq!(sty.foreground, Some(Colour::Fixed(160)));
+    let sty = ColorLevel::Ansi256.downsample(Colour::RGB(0x80, 0x80, 0x81).normal());
+    assert_eq!(sty.foreground, Some(Colour::Fixed(244)));
+    let sty = ColorLevel::Ansi256.downsample(Colour::Red.on(Colour::Fixed(250)));
+    assert_eq!(sty, Colour::Red.on(Colour::Fixed(250)));
+}
+
+#[test]
+fn ansi16() {
+    let cases = [
+        (Colour::RGB(0xd7, 0x00, 0x00), Colour::Red),
+        (Colour::RGB(0x10, 0x10, 0x10), Colour::Black),
+        (Colour::RGB(0xff, 0xff, 0xff), Colour::White),
+        (Colour::Fixed(2), Colour::Green),
+        (Colour::Fixed(12), Colour::Fixed(12)),
+        (Colour::Fixed(51), Colour::Cyan),
+        (Colour::Fixed(200), Colour::Purple),
+        (Colour::Yellow, Colour::Yellow),
+    ];
+    for &(from, to) in &cases {
+        let sty = ColorLevel::Ansi16.downsample(from.normal());
+        assert_eq!(sty.foreground, Some(to), "{:?}", from);
+    }
+}
+
+#[test]
+fn rendering() {
+    let render = |level: ColorLevel| {
+        doc()
+            .display_with(RenderOptions::new(80).color(level))
+            .to_string()
+    };
+    assert_eq!(render(ColorLevel::None), "x");
+    assert_eq!(
+        render(ColorLevel::Ansi16),
+        Colour::Red.on(Colour::White).bold().paint("x").to_string()
+    );
+    assert_eq!(
+        render(ColorLevel::Ansi256),
+        Colour::Fixed(160)
+            .on(Colour::Fixed(250))
+            .bold()
+            .paint("x")
+            .to_string()
+    );
+}
+
+#[test]
+fn ansi16_escapes() {
+    let render = |sty: Style| {
+        Doc::text("hi", sty)
+            .display_opts(80, ColorLevel::Ansi16)
+            .to_string()
+    };
+    assert_eq!(
+        render(Colour::RGB(0xff, 0x00, 0x00).normal()),
+        "\x1b[31mhi\x1b[0m"
+    );
+    assert_eq!(render(Colour::Fixed(200).normal()), "\x1b[35mhi\x1b[0m");
+    assert_eq!(render(Colour::Fixed(9).normal()), "\x1b[91mhi\x1b[0m");
+    assert_eq!(
+        render(Colour::Fixed(9).on(Colour::Fixed(12)).bold()),
+        "\x1b[1;91;104mhi\x1b[0m"
+    );
+    assert_eq!(
+        render(Colour::Fixed(15).on(Colour::Blue)),
+        "\x1b[44;97mhi\x1b[0m"
+    );
+}
+
+#[test]
+fn none_keeps_other_properties() {
+    let sty = ColorLevel::None.downsample(Colour::Red.bold());
+    assert_eq!(sty, Style::new().bold());
+}
@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use proptest::prelude::*;
+
+use {Doc, FromDebug, Sparkly};
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Line(Point, Point);
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Named {
+    name: &'static str,
+    tag: char,
+    marker: PhantomData<BTreeMap<u8, Vec<u8>>>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+enum Shape {
+    Empty,
+    Polygon { points: Vec<Point>, closed: bool },
+}
+
+fn named() -> Named {
+    Named {
+        name: "a \"quoted\", {tricky} (string)",
+        tag: '\'',
+        marker: PhantomData,
+    }
+}
+
+fn polygon() -> Shape {
+    Shape::Polygon {
+        points: vec![Point { x: 0, y: 0 }, Point { x: 10, y: 0 }],
+        closed: true,
+    }
+}
+
+tests! {
+    [small_struct, 80, false] FromDebug(Point { x: 1, y: 2 }) => "Point { x: 1, y: 2 }",
+    [broken_struct, 10, false] FromDebug(Point { x: 1, y: 2 }) =>
+        "Point {\n    x: 1,\n    y: 2,\n}",
+    [tuple_struct, 25, false] FromDebug(Line(Point { x: 1, y: 2 }, Point { x: 3, y: 4 })) =>
+        "Line(\n    Point { x: 1, y: 2 },\n    Point { x: 3, y: 4 },\n)",
+    [unit_variant, 80, false] FromDebug(Shape::Empty) => "Empty",
+    [nested, 30, false] FromDebug(polygon()) =>
+        "Polygon {\n    points: [\n        Point { x: 0, y: 0 },\n        Point { x: 10, y: 0 },\n    ],\n    closed: true,\n}",
+    [strings_and_generics, 80, false] FromDebug(named()) =>
+        "Named {\n    name: \"a \\\"quoted\\\", {tricky} (string)\",\n    tag: '\\'',\n    marker: PhantomData<alloc::collections::btree::map::BTreeMap<u8, alloc::vec::Vec<u8>>>,\n}",
+    [one_tuple, 80, false] FromDebug((1,)) => "(1,)",
+    [empty_vec, 80, false] FromDebug(Vec::<u8>::new()) => "[]",
+    [unbalanced, 80, false] FromDebug(Unbalanced) => "a) (b [c",
+    [unbalanced_broken, 3, false] FromDebug(Unbalanced) => "a) (\n    b [\n        c"
+}
+
+/// A `Debug` impl that doesn't write valid `{:?}` syntax.
+struct Unbalanced;
+
+impl ::std::fmt::Debug for Unbalanced {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        fmt.write_str("a) (b [c")
+    }
+}
+
+/// A `Debug` impl that writes lists nested `n` deep, which would overflow the
+/// stack if it were written by recursing.
+struct Nested(usize);
+
+impl ::std::fmt::Debug for Nested {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        fmt.write_str(&"[1, ".repeat(self.0))?;
+        fmt.write_str(&"]".repeat(self.0))
+    }
+}
+
+#[test]
+fn deep_nesting() {
+    let doc: Doc = Doc::from_debug(&Nested(100_000));
+    let flat = format!("{:?}", Nested(100_000));
+    assert_eq!(doc.display_opts(usize::MAX, false).to_string(), flat);
+
+    // The outermost 128 lists are broken, and the rest are kept as text on
+    // one line.
+    let broken = doc.display_opts(80, false).to_string();
+    let lines = broken.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 128 * 3 + 1);
+    let indent = " ".repeat(128 * 4);
+    assert_eq!(lines[128 * 2 - 1], format!("{}1,", indent));
+    assert_eq!(
+        lines[128 * 2],
+        format!("{}{},", indent, &flat[128 * 4..flat.len() - 128])
+    );
+    assert_eq!(lines[128 * 3], "]");
+}
+
+#[test]
+fn from_debug_matches_sparkly() {
+    let doc: Doc = Doc::from_debug(&vec![Some(1), None]);
+    assert_eq!(doc.display_opts(80, false).to_string(), "[Some(1), None]");
+}
+
+proptest! {
+    #[test]
+    fn flat_matches_debug(ref v in prop::collection::vec((any::<i32>(), ".*", any::<Option<char>>()), 0..8)) {
+        let doc: Doc = Doc::from_debug(v);
+        assert_eq!(doc.display_opts(usize::MAX, false).to_string(), format!("{:?}", v));
+    }
+
+    #[test]
+    fn broken_matches_pretty_debug(ref v in prop::collection::vec((any::<i32>(), ".*", any::<Option<char>>()), 0..8)) {
+        let doc: Doc = Doc::from_debug(v);
+        assert_eq!(doc.display_opts(0, false).to_string(), format!("{:#?}", v));
+    }
+
+    #[test]
+    fn lines_fit(ref v in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..4), 0..8)) {
+        let doc: Doc = Doc::from_debug(v);
+        let s = doc.display_opts(20, false).to_string();
+        assert!(s.lines().all(|line| line.len() <= 20), "{}", s);
+    }
+}
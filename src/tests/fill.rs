@@ -0,0 +1,42 @@
+use proptest::prelude::*;
+
+use tests::words;
+use {Doc, Sparkly};
+
+tests! {
+    [fill_empty, 80, false] Doc::fill_sep(Vec::<Doc>::new()) => "",
+    [fill_fits, 80, false]
+        Doc::fill_sep(words(vec!["the", "quick", "brown", "fox"])) =>
+        "the quick brown fox",
+    [fill_wraps, 15, false]
+        Doc::fill_sep(words(vec![
+            "the", "quick", "brown", "fox", "jumps", "over", "the", "lazy", "dog",
+        ])) =>
+        "the quick brown\nfox jumps over\nthe lazy dog",
+    [fill_separator, 12, false]
+        Doc::from(",").append(Doc::space())
+            .fill(words(vec!["aaa", "bbb", "ccc", "ddd"])) =>
+        "aaa, bbb,\nccc, ddd",
+    [fill_nested, 16, false]
+        Doc::from("f(")
+            .append(Doc::fill_sep(words(vec!["aaa", "bbb", "ccc", "ddd", "eee"])).align())
+            .append(Doc::from(")")) =>
+        "f(aaa bbb ccc\n  ddd eee)"
+}
+
+proptest! {
+    #[test]
+    fn fill_is_greedy(ref ws in prop::collection::vec("[a-z]{1,8}", 0..32), width in 8usize..40) {
+        let doc = Doc::fill_sep(ws.iter().cloned().map(Doc::from));
+        let out = doc.display_opts(width, false).to_string();
+        let lines = out.lines().collect::<Vec<_>>();
+        for l in &lines {
+            assert!(l.len() <= width);
+        }
+        for pair in lines.windows(2) {
+            let next = pair[1].split(' ').next().unwrap();
+            assert!(pair[0].len() + 1 + next.len() > width);
+        }
+        assert_eq!(out.split_whitespace().collect::<Vec<_>>(), *ws);
+    }
+}
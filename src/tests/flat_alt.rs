@@ -0,0 +1,72 @@
+use {Doc, LayoutMode, RenderOptions};
+
+/// Renders the `Doc` with both the greedy and the optimal layout.
+fn render(doc: &Doc, width: usize) -> (String, String) {
+    let greedy = doc.display_opts(width, false).to_string();
+    let opts = RenderOptions::new(width).mode(LayoutMode::Optimal);
+    (greedy, doc.display_with(opts).to_string())
+}
+
+/// A list with a comma after its last element only when it is broken.
+fn list(items: &[&'static str]) -> Doc {
+    let body = Doc::from(",")
+        .append(Doc::space())
+        .join(items.iter().map(|&item| Doc::from(item)))
+        .append(Doc::from(",").flat_alt(Doc::empty()));
+    Doc::from("[")
+        .append(Doc::split_point().append(body).nest(2))
+        .append(Doc::split_point())
+        .append(Doc::from("]"))
+        .group()
+}
+
+fn both(s: &str) -> (String, String) {
+    (s.to_string(), s.to_string())
+}
+
+#[test]
+fn flat_side_when_flattened() {
+    assert_eq!(render(&list(&["a", "b"]), 80), both("[a, b]"));
+}
+
+#[test]
+fn broken_side_when_broken() {
+    assert_eq!(render(&list(&["a", "b"]), 4), both("[\n  a,\n  b,\n]"));
+}
+
+#[test]
+fn broken_side_outside_groups() {
+    let doc = Doc::from("a").flat_alt(Doc::from("b"));
+    assert_eq!(render(&doc, 80), both("a"));
+}
+
+#[test]
+fn nested_groups() {
+    let doc = Doc::from("let x =")
+        .append(Doc::space().append(list(&["aaaaaa", "bbbbbb"])).nest(2))
+        .group();
+    assert_eq!(render(&doc, 20), both("let x =\n  [aaaaaa, bbbbbb]"));
+    assert_eq!(
+        render(&doc, 10),
+        both("let x =\n  [\n    aaaaaa,\n    bbbbbb,\n  ]")
+    );
+}
+
+#[test]
+fn fits_measures_flat_side() {
+    let doc = Doc::from("ab").flat_alt(Doc::from("abcdef")).group();
+    assert_eq!(render(&doc, 5), both("ab"));
+    // Both sides fit on one line, so the optimal layout takes the narrower.
+    assert_eq!(render(&doc, 6), ("abcdef".to_string(), "ab".to_string()));
+}
+
+#[test]
+fn fits_measures_broken_side_after_group() {
+    let doc = Doc::from("a")
+        .append(Doc::space())
+        .append(Doc::from("b"))
+        .group()
+        .append(Doc::from("ccc").flat_alt(Doc::empty()));
+    assert_eq!(render(&doc, 6), both("a bccc"));
+    assert_eq!(render(&doc, 5), both("a\nbccc"));
+}
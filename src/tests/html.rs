@@ -0,0 +1,88 @@
+use {Colour, Doc, HtmlStyles, Style};
+
+fn bold_classes(sty: &Style) -> String {
+    if sty.is_bold {
+        "kw".to_string()
+    } else {
+        String::new()
+    }
+}
+
+#[test]
+fn escapes() {
+    let doc = Doc::from("a < b && c > \"d\"");
+    assert_eq!(
+        doc.html(80).to_string(),
+        "a &lt; b &amp;&amp; c &gt; &quot;d&quot;"
+    );
+}
+
+#[test]
+fn inline_css() {
+    let doc = Doc::text("if", Colour::Purple.bold()).append(Doc::from(" x"));
+    assert_eq!(
+        doc.html(80).to_string(),
+        "<span style=\"color:#800080;font-weight:bold\">if</span> x"
+    );
+}
+
+#[test]
+fn inline_css_colours() {
+    let doc = Doc::text("a", Colour::Fixed(196).on(Colour::RGB(1, 2, 3)).underline());
+    assert_eq!(
+        doc.html(80).to_string(),
+        "<span style=\"color:#ff0000;background-color:#010203;text-decoration:underline\">a</span>"
+    );
+}
+
+#[test]
+fn classes() {
+    let doc = Doc::text("a", Colour::Red.on(Colour::Fixed(4)).italic());
+    assert_eq!(
+        doc.html(80).styles(HtmlStyles::classes()).to_string(),
+        "<span class=\"fg-red bg-4 italic\">a</span>"
+    );
+}
+
+#[test]
+fn custom_classes() {
+    let doc = Doc::text("fn", Style::new().bold())
+        .append(Doc::from(" "))
+        .append(Doc::text("f", Colour::Blue.normal()));
+    assert_eq!(
+        doc.html(80)
+            .styles(HtmlStyles::Classes(bold_classes))
+            .to_string(),
+        "<span class=\"kw\">fn</span> f"
+    );
+}
+
+#[test]
+fn nested_across_lines() {
+    let inner = Doc::from("b")
+        .append(Doc::line())
+        .append(Doc::from("c"))
+        .style(Style::new().bold());
+    let doc = Doc::from("a")
+        .append(inner)
+        .append(Doc::from("d"))
+        .nest(2)
+        .style(Colour::Red.normal());
+    assert_eq!(
+        doc.html(80).to_string(),
+        concat!(
+            "<span style=\"color:#800000\">a",
+            "<span style=\"font-weight:bold\">b</span></span>\n",
+            "  <span style=\"color:#800000\"><span style=\"font-weight:bold\">c</span>d</span>"
+        )
+    );
+}
+
+#[test]
+fn no_empty_spans() {
+    let doc = Doc::from("a")
+        .append(Doc::line().style(Colour::Red.normal()))
+        .append(Doc::from("b"))
+        .append(Doc::empty().style(Colour::Red.normal()));
+    assert_eq!(doc.html(80).to_string(), "a\nb");
+}
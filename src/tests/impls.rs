@@ -0,0 +1,74 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::Path;
+use std::rc::Rc;
+
+use proptest::prelude::*;
+
+use {Doc, Sparkly};
+
+fn map() -> BTreeMap<&'static str, Vec<u32>> {
+    let mut map = BTreeMap::new();
+    map.insert("evens", vec![0, 2, 4]);
+    map.insert("odds", vec![1, 3, 5]);
+    map
+}
+
+tests! {
+    [integer, 80, false] 42u8 => "42",
+    [float, 80, false] 1.0f64 => "1.0",
+    [boolean, 80, false] true => "true",
+    [string, 80, false] "say \"hi\"\n\tbye".to_string() => "\"say \\\"hi\\\"\\n\\tbye\"",
+    [char_quote, 80, false] '\'' => "'\\''",
+    [char_newline, 80, false] '\n' => "'\\n'",
+    [unit, 80, false] () => "()",
+    [one_tuple, 80, false] (1,) => "(1,)",
+    [one_tuple_broken, 0, false] (1,) => "(\n    1,\n)",
+    [tuple, 80, false] (1, "a", 'b') => "(1, \"a\", 'b')",
+    [tuple_broken, 0, false] (1, 'b') => "(\n    1,\n    'b',\n)",
+    [some, 80, false] Some(Some(1)) => "Some(Some(1))",
+    [some_broken, 0, false] Some(1) => "Some(\n    1,\n)",
+    [none, 80, false] None::<u32> => "None",
+    [ok, 80, false] Ok::<u32, String>(1) => "Ok(1)",
+    [err, 80, false] Err::<u32, _>("no") => "Err(\"no\")",
+    [empty_vec, 80, false] Vec::<u32>::new() => "[]",
+    [empty_vec_narrow, 0, false] Vec::<u32>::new() => "[]",
+    [vec_flat, 80, false] vec![1, 2, 3] => "[1, 2, 3]",
+    [vec_broken, 5, false] vec![1, 2, 3] => "[\n    1,\n    2,\n    3,\n]",
+    [array, 80, false] [[1, 2], [3, 4]] => "[[1, 2], [3, 4]]",
+    [slice, 80, false] &[1, 2][..] => "[1, 2]",
+    [vec_deque, 80, false] (1..4).collect::<VecDeque<u32>>() => "[1, 2, 3]",
+    [btree_set, 80, false] vec![3, 1, 2].into_iter().collect::<BTreeSet<u32>>() => "{1, 2, 3}",
+    [btree_map_flat, 80, false] map() => "{\"evens\": [0, 2, 4], \"odds\": [1, 3, 5]}",
+    [btree_map_broken, 24, false] map() =>
+        "{\n    \"evens\": [0, 2, 4],\n    \"odds\": [1, 3, 5],\n}",
+    [btree_map_nested, 12, false] map() =>
+        "{\n    \"evens\": [\n        0,\n        2,\n        4,\n    ],\n    \"odds\": [\n        1,\n        3,\n        5,\n    ],\n}",
+    [hash_map, 80, false] Some(1).into_iter().map(|n| (n, n)).collect::<HashMap<u32, u32>>() => "{1: 1}",
+    [boxed, 80, false] Box::new(1) => "1",
+    [rc_str, 80, false] Rc::<str>::from("a") => "\"a\"",
+    [cow, 80, false] Cow::Borrowed("a") => "\"a\"",
+    [path, 80, false] Path::new("/tmp/a b") => "\"/tmp/a b\""
+}
+
+#[test]
+fn impls_are_generic_over_annotations() {
+    let doc: Doc<()> = vec![Some(1), None].to_doc();
+    let mut out = String::new();
+    doc.render_fmt(&mut out, ::RenderOptions::new(80)).unwrap();
+    assert_eq!(out, "[Some(1), None]");
+}
+
+proptest! {
+    #[test]
+    fn flat_matches_debug(ref v in prop::collection::vec((any::<i32>(), ".*", any::<Option<char>>()), 0..8)) {
+        let doc: Doc = v.to_doc();
+        assert_eq!(doc.display_opts(usize::MAX, false).to_string(), format!("{:?}", v));
+    }
+
+    #[test]
+    fn broken_matches_pretty_debug(ref v in prop::collection::vec((any::<i32>(), ".*", any::<Option<char>>()), 0..8)) {
+        let doc: Doc = v.to_doc();
+        assert_eq!(doc.display_opts(0, false).to_string(), format!("{:#?}", v));
+    }
+}
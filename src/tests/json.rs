@@ -0,0 +1,250 @@
+use proptest::prelude::*;
+
+use json::{parse, reformat, Options, Styles, Value};
+use {Colour, LayoutMode, RenderOptions, Sparkly};
+
+const TEXT: &str = r#"
+{
+    "name": "caf\u00e9 \"bar\"",
+    "sizes": [1, 2.50, -3e+10],
+    "nested": {"empty": [], "none": {}, "ok": true, "nothing": null},
+    "zed": [[1, 2], [3, 4]]
+}
+"#;
+
+fn render(opts: &Options, width: usize) -> String {
+    reformat(TEXT, opts)
+        .unwrap()
+        .display_opts(width, false)
+        .to_string()
+}
+
+#[test]
+fn flat() {
+    assert_eq!(
+        render(&Options::new(), 200),
+        concat!(
+            r#"{"name": "caf\u00e9 \"bar\"", "sizes": [1, 2.50, -3e+10], "#,
+            r#""nested": {"empty": [], "none": {}, "ok": true, "nothing": null}, "#,
+            r#""zed": [[1, 2], [3, 4]]}"#
+        )
+    );
+}
+
+#[test]
+fn broken() {
+    assert_eq!(
+        render(&Options::new(), 40),
+        concat!(
+            "{\n",
+            "  \"name\": \"caf\\u00e9 \\\"bar\\\"\",\n",
+            "  \"sizes\": [1, 2.50, -3e+10],\n",
+            "  \"nested\": {\n",
+            "    \"empty\": [],\n",
+            "    \"none\": {},\n",
+            "    \"ok\": true,\n",
+            "    \"nothing\": null\n",
+            "  },\n",
+            "  \"zed\": [[1, 2], [3, 4]]\n",
+            "}"
+        )
+    );
+}
+
+#[test]
+fn options() {
+    let opts = Options::new()
+        .indent(4)
+        .sort_keys(true)
+        .trailing_commas(true);
+    assert_eq!(
+        render(&opts, 40),
+        concat!(
+            "{\n",
+            "    \"name\": \"caf\\u00e9 \\\"bar\\\"\",\n",
+            "    \"nested\": {\n",
+            "        \"empty\": [],\n",
+            "        \"none\": {},\n",
+            "        \"nothing\": null,\n",
+            "        \"ok\": true,\n",
+            "    },\n",
+            "    \"sizes\": [1, 2.50, -3e+10],\n",
+            "    \"zed\": [[1, 2], [3, 4]],\n",
+            "}"
+        )
+    );
+}
+
+#[test]
+fn trailing_commas_with_optimal_layout() {
+    let doc = reformat("[[1, 2], [3, 4]]", &Options::new().trailing_commas(true)).unwrap();
+    let opts = RenderOptions::new(10).mode(LayoutMode::Optimal);
+    assert_eq!(
+        doc.display_with(opts).to_string(),
+        "[\n  [1, 2],\n  [3, 4],\n]"
+    );
+}
+
+#[test]
+fn styles() {
+    let opts = Options::new().styles(Styles {
+        key: Colour::Blue.normal(),
+        string: Colour::Green.normal(),
+        ..Styles::default()
+    });
+    assert_eq!(
+        reformat(r#"{"a":"b"}"#, &opts)
+            .unwrap()
+            .display_opts(80, true)
+            .to_string(),
+        format!(
+            "{{{}: {}}}",
+            Colour::Blue.paint("\"a\""),
+            Colour::Green.paint("\"b\"")
+        )
+    );
+}
+
+#[test]
+fn errors() {
+    let cases: &[(&str, usize)] = &[
+        ("", 0),
+        ("[1, 2", 5),
+        ("[1 2]", 3),
+        ("{\"a\" 1}", 5),
+        ("{1: 2}", 1),
+        ("01", 1),
+        ("1.", 2),
+        ("-", 1),
+        ("\"\\x\"", 2),
+        ("\"\\u12\"", 3),
+        ("\"a\nb\"", 2),
+        ("tru", 0),
+        ("[1,]", 3),
+        ("1 2", 2),
+    ];
+    for &(text, offset) in cases {
+        assert_eq!(parse(text).map_err(|e| e.offset), Err(offset), "{:?}", text);
+    }
+    assert_eq!(
+        parse("[1 2]").unwrap_err().to_string(),
+        "expected ',' or ']' at byte 3"
+    );
+}
+
+#[test]
+fn deep_nesting() {
+    let nested = |n: usize| format!("{}{}", "[".repeat(n), "]".repeat(n));
+    let doc = reformat(&nested(128), &Options::new()).unwrap();
+    assert_eq!(doc.display_opts(256, false).to_string(), nested(128));
+
+    assert_eq!(parse(&nested(129)).map_err(|e| e.offset), Err(128));
+    assert_eq!(
+        parse(r#"{"a": [{"b": 1}]}"#.replace("1", &nested(126)).as_str()).map_err(|e| e.offset),
+        Err(138)
+    );
+    assert_eq!(
+        parse(&nested(100_000)).unwrap_err().to_string(),
+        "recursion limit exceeded at byte 128"
+    );
+}
+
+#[test]
+fn sparkly_impl() {
+    let value = Value::Array(vec![Value::Number("1e2".to_string()), Value::Null]);
+    assert_eq!(
+        value.to_doc().display_opts(80, false).to_string(),
+        "[1e2, null]"
+    );
+}
+
+fn arb_value() -> BoxedStrategy<Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        "-?(0|[1-9][0-9]{0,5})(\\.[0-9]{1,3})?([eE][+-]?[0-9]{1,2})?".prop_map(Value::Number),
+        r#"([^"\\\x00-\x1f]|\\[nt"\\/]|\\u[0-9a-fA-F]{4}){0,8}"#.prop_map(Value::String),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+            prop::collection::vec(("[a-z]{0,4}", inner), 0..6).prop_map(Value::Object),
+        ]
+        .boxed()
+    })
+    .boxed()
+}
+
+fn sorted(value: &Value) -> Value {
+    match *value {
+        Value::Array(ref values) => Value::Array(values.iter().map(sorted).collect()),
+        Value::Object(ref entries) => {
+            let mut entries = entries
+                .iter()
+                .map(|(k, v)| (k.clone(), sorted(v)))
+                .collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(entries)
+        }
+        ref value => value.clone(),
+    }
+}
+
+/// Removes the commas outside of strings that come right before a closing
+/// bracket.
+fn strip_trailing_commas(s: &str) -> String {
+    let mut out = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ','
+            && chars
+                .clone()
+                .find(|c| !c.is_whitespace())
+                .is_some_and(|c| c == ']' || c == '}')
+        {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+proptest! {
+    #[test]
+    fn round_trips(ref value in arb_value(), width in 0usize..80, indent in 0usize..6) {
+        let doc = value.to_doc_with(&Options::new().indent(indent));
+        let s = doc.display_opts(width, false).to_string();
+        assert_eq!(&parse(&s).unwrap(), value);
+    }
+
+    #[test]
+    fn reformatting_is_idempotent(ref value in arb_value(), width in 0usize..80) {
+        let opts = Options::new().sort_keys(true);
+        let once = value.to_doc_with(&opts).display_opts(width, false).to_string();
+        let twice = reformat(&once, &opts).unwrap().display_opts(width, false).to_string();
+        assert_eq!(once, twice);
+        assert_eq!(parse(&once).unwrap(), sorted(value));
+    }
+
+    #[test]
+    fn trailing_commas_round_trip(ref value in arb_value(), width in 0usize..80) {
+        let doc = value.to_doc_with(&Options::new().trailing_commas(true));
+        let s = doc.display_opts(width, false).to_string();
+        if !s.contains('\n') {
+            assert_eq!(&parse(&s).unwrap(), value);
+        }
+        assert_eq!(&parse(&strip_trailing_commas(&s)).unwrap(), value);
+    }
+}
@@ -0,0 +1,95 @@
+use {Colour, Doc, Line, RenderOptions, Style};
+
+fn call() -> Doc {
+    Doc::text("f", Colour::Blue.normal())
+        .append(Doc::from("("))
+        .append(
+            Doc::split_point()
+                .append(Doc::text("x", Colour::Green.normal()))
+                .append(Doc::from(","))
+                .append(Doc::space())
+                .append(Doc::from("y"))
+                .nest(4),
+        )
+        .append(Doc::split_point())
+        .append(Doc::from(")"))
+        .group()
+}
+
+#[test]
+fn one_line() {
+    assert_eq!(
+        call().layout(80),
+        vec![Line {
+            indent: 0,
+            spans: vec![
+                ("f".to_string(), Colour::Blue.normal()),
+                ("(".to_string(), Style::new()),
+                ("x".to_string(), Colour::Green.normal()),
+                (", y)".to_string(), Style::new()),
+            ],
+        }]
+    );
+}
+
+#[test]
+fn broken() {
+    let lines = call().layout(4);
+    assert_eq!(
+        lines,
+        vec![
+            Line {
+                indent: 0,
+                spans: vec![
+                    ("f".to_string(), Colour::Blue.normal()),
+                    ("(".to_string(), Style::new()),
+                ],
+            },
+            Line {
+                indent: 4,
+                spans: vec![
+                    ("x".to_string(), Colour::Green.normal()),
+                    (",".to_string(), Style::new()),
+                ],
+            },
+            Line {
+                indent: 4,
+                spans: vec![("y".to_string(), Style::new())],
+            },
+            Line {
+                indent: 0,
+                spans: vec![(")".to_string(), Style::new())],
+            },
+        ]
+    );
+    assert_eq!(
+        lines.iter().map(Line::width).collect::<Vec<_>>(),
+        vec![2, 6, 5, 1]
+    );
+}
+
+#[test]
+fn matches_display() {
+    for w in 1..20 {
+        let opts = RenderOptions::new(w);
+        let text = call()
+            .layout_with(opts)
+            .iter()
+            .map(|line| {
+                let spans: String = line.spans.iter().map(|s| &s.0[..]).collect();
+                format!("{}{}", " ".repeat(line.indent), spans)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(text, call().display_with(opts).to_string());
+    }
+}
+
+#[test]
+fn empty() {
+    assert_eq!(Doc::empty().layout(80), vec![Line::default()]);
+    assert_eq!(
+        Doc::line().layout(80),
+        vec![Line::default(), Line::default()]
+    );
+}
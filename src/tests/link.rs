@@ -0,0 +1,110 @@
+use {Colour, Doc, LayoutMode, RenderOptions};
+
+const URL: &str = "https://example.com/a/rather/long/url/that/would/not/fit";
+
+fn open(url: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\", url)
+}
+
+fn close() -> String {
+    open("")
+}
+
+fn words() -> Doc {
+    Doc::from("see")
+        .append(Doc::space())
+        .append(Doc::from("the"))
+        .append(Doc::space())
+        .append(Doc::from("docs"))
+        .link(URL)
+        .nest(2)
+        .group()
+}
+
+#[test]
+fn plain_drops_links() {
+    assert_eq!(words().display_opts(80, false).to_string(), "see the docs");
+}
+
+#[test]
+fn ansi_emits_osc8() {
+    assert_eq!(
+        words().display_opts(80, true).to_string(),
+        format!("{}see the docs{}", open(URL), close())
+    );
+}
+
+#[test]
+fn links_take_no_width() {
+    // The URL is much longer than the page, but the text fits exactly.
+    for &mode in &[LayoutMode::Greedy, LayoutMode::Optimal] {
+        let opts = RenderOptions::new(12).color(true).mode(mode);
+        assert_eq!(
+            words().display_with(opts).to_string(),
+            format!("{}see the docs{}", open(URL), close())
+        );
+    }
+}
+
+#[test]
+fn links_survive_line_breaks() {
+    for &mode in &[LayoutMode::Greedy, LayoutMode::Optimal] {
+        let opts = RenderOptions::new(5).color(true).mode(mode);
+        assert_eq!(
+            words().display_with(opts).to_string(),
+            format!(
+                "{o}see{c}\n  {o}the{c}\n  {o}docs{c}",
+                o = open(URL),
+                c = close()
+            )
+        );
+    }
+}
+
+#[test]
+fn nested_links() {
+    let doc = Doc::from("a")
+        .append(Doc::from("b").link("inner"))
+        .append(Doc::from("c"))
+        .link("outer");
+    assert_eq!(
+        doc.display_opts(80, true).to_string(),
+        format!(
+            "{}a{}b{}c{}",
+            open("outer"),
+            open("inner"),
+            open("outer"),
+            close()
+        )
+    );
+}
+
+#[test]
+fn links_and_styles() {
+    let doc = Doc::text("x", Colour::Red.normal()).link("u");
+    assert_eq!(
+        doc.display_opts(80, true).to_string(),
+        format!(
+            "{}{}x{}{}",
+            open("u"),
+            Colour::Red.prefix(),
+            close(),
+            Colour::Red.suffix()
+        )
+    );
+}
+
+#[test]
+fn html_links() {
+    let doc = Doc::text("x", Colour::Red.normal())
+        .append(Doc::line())
+        .append(Doc::from("y"))
+        .link("/a?b=1&c=2");
+    assert_eq!(
+        doc.html(80).to_string(),
+        concat!(
+            "<a href=\"/a?b=1&amp;c=2\"><span style=\"color:#800000\">x</span></a>\n",
+            "<a href=\"/a?b=1&amp;c=2\">y</a>"
+        )
+    );
+}
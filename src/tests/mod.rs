@@ -1,5 +1,7 @@
-use {Doc, DocInner, Sparkly};
+#[cfg(feature = "std")]
+use {Doc, DocInner, Ptr, Sparkly};
 
+#[cfg(feature = "std")]
 macro_rules! tests {
     ($([$name:ident, $n:expr, $c:expr] $l:expr => $r:expr),*) => {
         $(#[test] fn $name() {
@@ -9,15 +11,84 @@ macro_rules! tests {
     };
 }
 
+/// Makes a `Doc` of each word.
+#[cfg(feature = "std")]
+fn words(ws: Vec<&'static str>) -> Vec<Doc> {
+    ws.into_iter().map(Doc::from).collect()
+}
+
+#[cfg(feature = "std")]
+mod align;
+#[cfg(feature = "std")]
+mod annotate;
+#[cfg(feature = "std")]
+mod ansi;
+#[cfg(feature = "std")]
+mod color;
+#[cfg(feature = "std")]
+mod debug;
+#[cfg(feature = "std")]
+mod fill;
+#[cfg(feature = "std")]
+mod flat_alt;
+#[cfg(feature = "std")]
+mod html;
+#[cfg(feature = "std")]
 mod impl_bounds;
+#[cfg(feature = "std")]
+mod impls;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod layout;
+#[cfg(feature = "std")]
+mod link;
+#[cfg(feature = "std")]
+mod optimal;
+mod render_fmt;
+#[cfg(feature = "std")]
+mod ribbon;
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(feature = "std")]
 mod sexpr;
+mod source_map;
+#[cfg(feature = "std")]
+mod style;
+#[cfg(feature = "std")]
+mod terminal;
+#[cfg(feature = "std")]
+mod view;
+#[cfg(feature = "std")]
+mod width;
 
+#[cfg(feature = "std")]
 tests! {
     [misc_1, 80, false]
         Doc::from("asdf")
-            .append(Doc::from(DocInner::Nest(2, Box::new(DocInner::Line(None)))))
+            .append(Doc::from(DocInner::Nest(2, Ptr::new(DocInner::Line(None)))))
             .append(Doc::from("qwerty"))
-            .append(Doc::from(DocInner::Nest(4, Box::new(DocInner::Line(None)))))
+            .append(Doc::from(DocInner::Nest(4, Ptr::new(DocInner::Line(None)))))
             .append(Doc::from("zxcvbn")) =>
-        "asdf\n  qwerty\n    zxcvbn"
+        "asdf\n  qwerty\n    zxcvbn",
+    [long_indent, 80, false]
+        Doc::from("a")
+            .append(Doc::from(DocInner::Nest(100, Ptr::new(DocInner::Line(None)))))
+            .append(Doc::from("b")) =>
+        &format!("a\n{}b", " ".repeat(100)),
+    [bracket_flat, 80, false]
+        Doc::space().join(vec!["foo", "bar", "baz"].into_iter().map(Doc::from)).bracket("(", ")") =>
+        "(foo bar baz)",
+    [bracket_broken, 10, false]
+        Doc::space()
+            .join(vec!["foo", "bar", "baz", "quux", "spam", "eggs"].into_iter().map(Doc::from))
+            .bracket("(", ")") =>
+        "(\n    foo\n    bar\n    baz\n    quux\n    spam\n    eggs\n)"
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn doc_is_send_and_sync() {
+    fn check<T: Send + Sync>() {}
+    check::<::Doc>();
 }
@@ -0,0 +1,85 @@
+use proptest::prelude::*;
+
+use {Doc, LayoutMode, RenderOptions};
+
+/// Renders the `Doc` with the optimal layout.
+fn optimal(doc: &Doc, width: usize) -> String {
+    let opts = RenderOptions::new(width).mode(LayoutMode::Optimal);
+    doc.display_with(opts).to_string()
+}
+
+/// Counts the characters past the given width.
+fn overflow(s: &str, width: usize) -> usize {
+    s.lines().map(|l| l.len().saturating_sub(width)).sum()
+}
+
+/// A group that fits up to its first hard line break, but not after it.
+fn late_overflow() -> Doc {
+    Doc::from("a")
+        .append(Doc::space())
+        .append(Doc::from("b"))
+        .append(Doc::line())
+        .append(Doc::from("cccccccccc"))
+        .append(Doc::space())
+        .append(Doc::from("d"))
+        .group()
+}
+
+#[test]
+fn greedy_overflows() {
+    assert_eq!(
+        late_overflow().display_opts(10, false).to_string(),
+        "a b\ncccccccccc d"
+    );
+}
+
+#[test]
+fn optimal_avoids_overflow() {
+    assert_eq!(optimal(&late_overflow(), 10), "a\nb\ncccccccccc\nd");
+}
+
+#[test]
+fn optimal_prefers_fewer_lines() {
+    let doc = Doc::space()
+        .join(vec!["foo", "bar", "baz"].into_iter().map(Doc::from))
+        .bracket("(", ")");
+    assert_eq!(optimal(&doc, 80), "(foo bar baz)");
+    assert_eq!(optimal(&doc, 5), "(\n    foo\n    bar\n    baz\n)");
+}
+
+#[test]
+fn optimal_long_list() {
+    let doc = Doc::space()
+        .join((0..20_000).map(|i| Doc::from(i.to_string())))
+        .group();
+    let greedy = doc.display_opts(80, false).to_string();
+    assert_eq!(optimal(&doc, 80), greedy);
+    assert_eq!(greedy.lines().count(), 20_000);
+}
+
+fn arb_doc() -> BoxedStrategy<Doc> {
+    let leaf = prop_oneof![
+        "[a-z]{0,8}".prop_map(Doc::from),
+        Just(Doc::space()),
+        Just(Doc::line()),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(|ds| ds.into_iter().collect()),
+            inner.clone().prop_map(Doc::group),
+            (inner, 0usize..4).prop_map(|(d, n)| d.nest(n)),
+        ]
+    })
+    .boxed()
+}
+
+proptest! {
+    #[test]
+    fn optimal_never_worse(ref doc in arb_doc(), width in 0usize..20) {
+        let greedy = doc.display_opts(width, false).to_string();
+        let optimal = optimal(doc, width);
+        let words = |s: &str| s.split_whitespace().collect::<String>();
+        assert_eq!(words(&greedy), words(&optimal));
+        assert!(overflow(&optimal, width) <= overflow(&greedy, width));
+    }
+}
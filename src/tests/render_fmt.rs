@@ -0,0 +1,124 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use sexpr;
+use {Doc, LayoutMode, RenderOptions, Sparkly};
+
+fn render(doc: &Doc<()>, width: usize) -> String {
+    let mut out = String::new();
+    doc.render_fmt(&mut out, RenderOptions::new(width)).unwrap();
+    out
+}
+
+fn words(ws: &[&'static str]) -> Vec<Doc<()>> {
+    ws.iter().map(|&w| Doc::from(w)).collect()
+}
+
+#[test]
+fn groups() {
+    let doc = Doc::space()
+        .join(words(&["foo", "bar", "baz"]))
+        .bracket("(", ")");
+    assert_eq!(render(&doc, 80), "(foo bar baz)");
+    assert_eq!(render(&doc, 8), "(\n    foo\n    bar\n    baz\n)");
+}
+
+#[test]
+fn optimal_layout() {
+    let doc: Doc<()> = Doc::from("a")
+        .append(Doc::space())
+        .append(Doc::from("b"))
+        .append(Doc::line())
+        .append(Doc::from("cccccccccc"))
+        .append(Doc::space())
+        .append(Doc::from("d"))
+        .group();
+    let mut out = String::new();
+    let opts = RenderOptions::new(10).mode(LayoutMode::Optimal);
+    doc.render_fmt(&mut out, opts).unwrap();
+    assert_eq!(out, "a\nb\ncccccccccc\nd");
+}
+
+#[test]
+fn annotations_are_ignored() {
+    let doc: Doc<u8> = Doc::from("a")
+        .annotate(1)
+        .append(Doc::from("b").annotate(2).reset_style())
+        .link("https://example.com")
+        .tagged(3);
+    let mut out = String::new();
+    doc.render_fmt(&mut out, RenderOptions::new(80)).unwrap();
+    assert_eq!(out, "ab");
+}
+
+#[cfg(not(feature = "std"))]
+#[test]
+fn default_annotation_is_unit() {
+    let doc: Doc = Doc::from("a").annotate(());
+    assert_eq!(render(&doc, 80), "a");
+}
+
+#[test]
+fn constructors() {
+    let fill = Doc::fill_sep(words(&["aa", "bb", "cc", "dd"]));
+    assert_eq!(render(&fill, 5), "aa bb\ncc dd");
+
+    let lines = Doc::lines(words(&["a", "b"])).nest(2);
+    assert_eq!(render(&lines, 80), "a\n  b");
+
+    let aligned = Doc::from("(f ").append(Doc::space().join(words(&["x", "y"])).align());
+    assert_eq!(render(&aligned.group(), 4), "(f x\n   y");
+
+    let hung = Doc::space().join(words(&["a", "b"])).hang(2).group();
+    assert_eq!(render(&Doc::from("> ").append(hung), 3), "> a\n    b");
+
+    let indented = Doc::from("a").append(Doc::line()).append(Doc::from("b"));
+    assert_eq!(render(&indented.indent(2), 80), "  a\n  b");
+
+    let dedented = Doc::from("\"a")
+        .append(Doc::line())
+        .append(Doc::from("b\""))
+        .dedent();
+    assert_eq!(
+        render(&Doc::line().append(dedented).nest(4), 80),
+        "\n    \"a\nb\""
+    );
+
+    let positions = Doc::from("ab")
+        .append(Doc::column(|k| Doc::from(k.to_string())))
+        .append(Doc::line())
+        .append(Doc::nesting(|i| Doc::from(i.to_string())))
+        .nest(3);
+    assert_eq!(render(&positions, 80), "ab2\n   3");
+
+    let comma = Doc::from(",").flat_alt(Doc::empty());
+    let list = Doc::line_or(", ")
+        .join(words(&["a", "b"]))
+        .append(comma)
+        .bracket("[", "]");
+    assert_eq!(render(&list, 80), "[a, b]");
+    assert_eq!(render(&list, 3), "[\n    a\n    b,\n]");
+
+    assert_eq!(render(&Doc::from("a").append(Doc::nbsp()), 0), "a ");
+}
+
+#[test]
+fn sparkly_impls() {
+    let mut map = BTreeMap::new();
+    map.insert("a", vec![Some(1), None]);
+    let doc: Doc<()> = (map, 'x', "s".to_string()).to_doc();
+    assert_eq!(render(&doc, 80), "({\"a\": [Some(1), None]}, 'x', \"s\")");
+    assert_eq!(
+        render(&doc, 24),
+        "(\n    {\n        \"a\": [\n            Some(1),\n            None,\n        ],\n    },\n    'x',\n    \"s\",\n)"
+    );
+}
+
+#[test]
+fn sexprs() {
+    let expr = sexpr::parse("(let ((x 1)) (+ x 1))").unwrap();
+    assert_eq!(expr.to_string(), "(let ((x 1)) (+ x 1))");
+    let doc: Doc<()> = expr.to_doc();
+    assert_eq!(render(&doc, 12), "(let ((x 1))\n  (+ x 1))");
+}
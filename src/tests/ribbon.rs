@@ -0,0 +1,51 @@
+use {Doc, LayoutMode, RenderOptions};
+
+fn list() -> Doc {
+    Doc::space()
+        .join(vec!["aaaa", "bbbb", "cccc"].into_iter().map(Doc::from))
+        .bracket("(", ")")
+}
+
+fn render(doc: &Doc, opts: RenderOptions) -> String {
+    doc.display_with(opts).to_string()
+}
+
+#[test]
+fn no_ribbon() {
+    assert_eq!(render(&list(), RenderOptions::new(80)), "(aaaa bbbb cccc)");
+}
+
+#[test]
+fn ribbon_breaks() {
+    assert_eq!(
+        render(&list(), RenderOptions::new(80).ribbon(10)),
+        "(\n    aaaa\n    bbbb\n    cccc\n)"
+    );
+}
+
+#[test]
+fn ribbon_ignores_indentation() {
+    let doc = Doc::from("x").append(Doc::line().append(list()).nest(40));
+    assert_eq!(
+        render(&doc, RenderOptions::new(80).ribbon(16)),
+        format!("x\n{}(aaaa bbbb cccc)", " ".repeat(40))
+    );
+}
+
+#[test]
+fn ribbon_and_width() {
+    let doc = Doc::from("x").append(Doc::line().append(list()).nest(70));
+    assert_eq!(
+        render(&doc, RenderOptions::new(80).ribbon(16)),
+        format!(
+            "x\n{0}(\n{0}    aaaa\n{0}    bbbb\n{0}    cccc\n{0})",
+            " ".repeat(70)
+        )
+    );
+}
+
+#[test]
+fn ribbon_optimal() {
+    let opts = RenderOptions::new(80).ribbon(10).mode(LayoutMode::Optimal);
+    assert_eq!(render(&list(), opts), "(\n    aaaa\n    bbbb\n    cccc\n)");
+}
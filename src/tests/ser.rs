@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+
+use proptest::prelude::*;
+use ron;
+use serde::{Deserialize, Serialize};
+use serde_json::{self, Map, Number, Value};
+
+use ser::{to_doc, Serializer, Styles, Syntax};
+use {Colour, Style};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Config {
+    name: String,
+    retries: u32,
+    ratio: f64,
+    tags: Vec<String>,
+    mode: Mode,
+    limits: BTreeMap<String, (u8, Option<u8>)>,
+    parent: Option<Box<Config>>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+enum Mode {
+    Off,
+    Fixed(u32),
+    Range(u32, u32),
+    Custom { every: u32, jitter: bool },
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Id(u32);
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Marker;
+
+fn config() -> Config {
+    let mut limits = BTreeMap::new();
+    limits.insert("cpu".to_string(), (4, Some(8)));
+    limits.insert("mem".to_string(), (1, None));
+    Config {
+        name: "job \"a\"".to_string(),
+        retries: 3,
+        ratio: 0.5,
+        tags: vec!["x".to_string(), "y".to_string()],
+        mode: Mode::Custom {
+            every: 10,
+            jitter: true,
+        },
+        limits,
+        parent: None,
+    }
+}
+
+fn render<T: Serialize>(value: &T, syntax: Syntax, width: usize) -> String {
+    to_doc(value, syntax)
+        .unwrap()
+        .display_opts(width, false)
+        .to_string()
+}
+
+#[test]
+fn json_flat() {
+    assert_eq!(
+        render(&config(), Syntax::Json, 200),
+        concat!(
+            r#"{"name": "job \"a\"", "retries": 3, "ratio": 0.5, "tags": ["x", "y"], "#,
+            r#""mode": {"Custom": {"every": 10, "jitter": true}}, "#,
+            r#""limits": {"cpu": [4, 8], "mem": [1, null]}, "parent": null}"#
+        )
+    );
+}
+
+#[test]
+fn json_broken() {
+    assert_eq!(
+        render(&config(), Syntax::Json, 50),
+        concat!(
+            "{\n",
+            "    \"name\": \"job \\\"a\\\"\",\n",
+            "    \"retries\": 3,\n",
+            "    \"ratio\": 0.5,\n",
+            "    \"tags\": [\"x\", \"y\"],\n",
+            "    \"mode\": {\n",
+            "        \"Custom\": {\"every\": 10, \"jitter\": true}\n",
+            "    },\n",
+            "    \"limits\": {\"cpu\": [4, 8], \"mem\": [1, null]},\n",
+            "    \"parent\": null\n",
+            "}"
+        )
+    );
+}
+
+#[test]
+fn json_variants() {
+    assert_eq!(render(&Mode::Off, Syntax::Json, 80), r#""Off""#);
+    assert_eq!(render(&Mode::Fixed(1), Syntax::Json, 80), r#"{"Fixed": 1}"#);
+    assert_eq!(
+        render(&Mode::Range(1, 2), Syntax::Json, 80),
+        r#"{"Range": [1, 2]}"#
+    );
+    assert_eq!(render(&Id(7), Syntax::Json, 80), "7");
+    assert_eq!(render(&Marker, Syntax::Json, 80), "null");
+    assert_eq!(render(&f64::NAN, Syntax::Json, 80), "null");
+}
+
+#[test]
+fn json_keys() {
+    let mut map = BTreeMap::new();
+    map.insert(1, true);
+    assert_eq!(render(&map, Syntax::Json, 80), r#"{"1": true}"#);
+
+    let mut map = BTreeMap::new();
+    map.insert(vec![1], true);
+    assert_eq!(
+        to_doc(&map, Syntax::Json).unwrap_err().to_string(),
+        "JSON object keys must be strings"
+    );
+}
+
+#[test]
+fn json_round_trips() {
+    let config = Config {
+        parent: Some(Box::new(config())),
+        ..config()
+    };
+    for width in 0..100 {
+        let s = render(&config, Syntax::Json, width);
+        assert_eq!(serde_json::from_str::<Config>(&s).unwrap(), config);
+    }
+}
+
+#[test]
+fn ron_flat() {
+    assert_eq!(
+        render(&config(), Syntax::Ron, 200),
+        concat!(
+            r#"Config(name: "job \"a\"", retries: 3, ratio: 0.5, tags: ["x", "y"], "#,
+            r#"mode: Custom(every: 10, jitter: true), "#,
+            r#"limits: {"cpu": (4, Some(8)), "mem": (1, None)}, parent: None)"#
+        )
+    );
+}
+
+#[test]
+fn ron_broken() {
+    assert_eq!(
+        render(&config(), Syntax::Ron, 50),
+        concat!(
+            "Config(\n",
+            "    name: \"job \\\"a\\\"\",\n",
+            "    retries: 3,\n",
+            "    ratio: 0.5,\n",
+            "    tags: [\"x\", \"y\"],\n",
+            "    mode: Custom(every: 10, jitter: true),\n",
+            "    limits: {\n",
+            "        \"cpu\": (4, Some(8)),\n",
+            "        \"mem\": (1, None)\n",
+            "    },\n",
+            "    parent: None\n",
+            ")"
+        )
+    );
+}
+
+#[test]
+fn ron_values() {
+    assert_eq!(render(&Mode::Off, Syntax::Ron, 80), "Off");
+    assert_eq!(render(&Mode::Range(1, 2), Syntax::Ron, 80), "Range(1, 2)");
+    assert_eq!(render(&Id(7), Syntax::Ron, 80), "Id(7)");
+    assert_eq!(render(&Marker, Syntax::Ron, 80), "Marker");
+    assert_eq!(render(&(1,), Syntax::Ron, 80), "(1,)");
+    assert_eq!(render(&((), 'x', 1.0), Syntax::Ron, 80), "((), 'x', 1.0)");
+}
+
+#[test]
+fn floats() {
+    for &syntax in &[Syntax::Json, Syntax::Ron] {
+        assert_eq!(render(&0.1f32, syntax, 80), "0.1");
+        assert_eq!(render(&0.1f64, syntax, 80), "0.1");
+        assert_eq!(render(&1e20f32, syntax, 80), "1e20");
+        assert_eq!(render(&16777216f32, syntax, 80), "16777216.0");
+    }
+    assert_eq!(render(&f32::NAN, Syntax::Json, 80), "null");
+    assert_eq!(render(&f32::INFINITY, Syntax::Ron, 80), "inf");
+}
+
+#[test]
+fn styles() {
+    let ser = Serializer::new(Syntax::Json).styles(Styles {
+        key: Colour::Blue.normal(),
+        number: Colour::Cyan.normal(),
+        ..Styles::default()
+    });
+    let mut map = BTreeMap::new();
+    map.insert("a", 1);
+    let doc = map.serialize(&ser).unwrap();
+    assert_eq!(
+        doc.display_opts(80, true).to_string(),
+        format!(
+            "{{{}: {}}}",
+            Colour::Blue.paint("\"a\""),
+            Colour::Cyan.paint("1")
+        )
+    );
+    assert_eq!(Styles::default().punctuation, Style::new());
+}
+
+/// Removes the whitespace outside of strings and characters.
+fn squash(s: &str) -> String {
+    let mut out = String::new();
+    let mut quote = None;
+    let mut escaped = false;
+    for c in s.chars() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c.is_whitespace() => continue,
+            None => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn arb_json() -> BoxedStrategy<Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(|n| Value::Number(Number::from(n))),
+        ".*".prop_map(Value::String),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(Value::Array),
+            prop::collection::vec((".*", inner), 0..8)
+                .prop_map(|entries| Value::Object(entries.into_iter().collect::<Map<_, _>>())),
+        ]
+        .boxed()
+    })
+    .boxed()
+}
+
+fn arb_mode() -> BoxedStrategy<Mode> {
+    prop_oneof![
+        Just(Mode::Off),
+        any::<u32>().prop_map(Mode::Fixed),
+        (any::<u32>(), any::<u32>()).prop_map(|(a, b)| Mode::Range(a, b)),
+        (any::<u32>(), any::<bool>()).prop_map(|(every, jitter)| Mode::Custom { every, jitter }),
+    ]
+    .boxed()
+}
+
+fn arb_config() -> BoxedStrategy<Config> {
+    let config = |parent| {
+        (
+            (".*", any::<u32>(), -1e6..1e6f64),
+            prop::collection::vec("[a-z]{0,6}", 0..4),
+            arb_mode(),
+            prop::collection::btree_map(".{0,6}", any::<(u8, Option<u8>)>(), 0..4),
+            parent,
+        )
+            .prop_map(
+                |((name, retries, ratio), tags, mode, limits, parent)| Config {
+                    name,
+                    retries,
+                    ratio,
+                    tags,
+                    mode,
+                    limits,
+                    parent,
+                },
+            )
+    };
+    config(Just(None).boxed())
+        .prop_recursive(2, 4, 1, move |inner| {
+            config(prop::option::of(inner.prop_map(Box::new)).boxed())
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn ron_round_trips(ref config in arb_config()) {
+        for &width in &[0, 20, 40, 80, usize::MAX] {
+            let s = render(config, Syntax::Ron, width);
+            assert_eq!(&ron::from_str::<Config>(&s).unwrap(), config, "{}", s);
+        }
+    }
+
+    #[test]
+    fn ron_values_round_trip(ref value in arb_json(), width in 0usize..100) {
+        let s = render(value, Syntax::Ron, width);
+        assert_eq!(&ron::from_str::<Value>(&s).unwrap(), value, "{}", s);
+    }
+
+    #[test]
+    fn json_values_round_trip(ref value in arb_json(), width in 0usize..100) {
+        let s = render(value, Syntax::Json, width);
+        assert_eq!(&serde_json::from_str::<Value>(&s).unwrap(), value);
+    }
+
+    #[test]
+    fn ron_only_breaks_between_tokens(ref value in arb_json(), width in 0usize..100) {
+        let flat = render(value, Syntax::Ron, usize::MAX);
+        let broken = render(value, Syntax::Ron, width);
+        assert_eq!(squash(&broken), squash(&flat));
+    }
+}
@@ -1,24 +1,8 @@
 use proptest::prelude::*;
 
+use sexpr::{parse, parse_all, Options, SExpr};
 use {Doc, Sparkly};
 
-#[derive(Clone, Debug)]
-enum SExpr {
-    Atom(String),
-    List(Vec<SExpr>),
-}
-
-impl_Display_for_Sparkly!(SExpr);
-
-impl Sparkly for SExpr {
-    fn to_doc(&self) -> Doc {
-        match *self {
-            SExpr::Atom(ref s) => s.to_string().into(),
-            SExpr::List(ref l) => Doc::space().join(l).bracket("(", ")"),
-        }
-    }
-}
-
 macro_rules! sexpr {
     ($atom:ident) => {
         SExpr::Atom(stringify!($atom).to_string())
@@ -30,20 +14,50 @@ macro_rules! sexpr {
     };
 }
 
+fn render(text: &str, opts: &Options, width: usize) -> String {
+    let doc: Doc = parse(text).unwrap().to_doc_with(opts);
+    doc.display_opts(width, false).to_string()
+}
+
 fn arb_sexpr() -> BoxedStrategy<SExpr> {
-    let leaf = prop_oneof!["[a-zA-Z]+".prop_map(SExpr::Atom)];
+    let leaf = prop_oneof![
+        "[a-zA-Z]+".prop_map(SExpr::Atom),
+        "-?[0-9]{1,4}".prop_map(SExpr::Atom),
+        r#""([^"\\]|\\.)*""#.prop_map(SExpr::Atom),
+    ];
     leaf.prop_recursive(4, 256, 16, |inner| {
-        prop_oneof![prop::collection::vec(inner, 0..8).prop_map(SExpr::List)].boxed()
-    }).boxed()
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(SExpr::List),
+            (prop::sample::select(vec!["'", "`", ","]), inner)
+                .prop_map(|(prefix, expr)| SExpr::Prefixed(prefix, Box::new(expr))),
+        ]
+        .boxed()
+    })
+    .boxed()
 }
 
 proptest! {
     #[test]
     fn display_works(ref sexpr in arb_sexpr()) {
-        let doc = sexpr.to_doc();
+        let doc: Doc = sexpr.to_doc();
         let p = doc.display_opts(80, false);
         assert_eq!(p.to_string(), sexpr.to_string());
     }
+
+    #[test]
+    fn render_to_works(ref sexpr in arb_sexpr(), width in 0usize..100) {
+        let doc: Doc = sexpr.to_doc();
+        let mut out = Vec::new();
+        doc.render_to(&mut out, width, false).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), doc.display_opts(width, false).to_string());
+    }
+
+    #[test]
+    fn round_trips(ref sexpr in arb_sexpr(), width in 0usize..100) {
+        let doc: Doc = sexpr.to_doc();
+        let s = doc.display_opts(width, false).to_string();
+        assert_eq!(&parse(&s).unwrap(), sexpr);
+    }
 }
 
 tests! {
@@ -54,5 +68,134 @@ tests! {
         "(foo bar baz quux spam eggs)",
     [wrapping, 10, false]
         sexpr![(foo bar baz quux spam eggs)] =>
-        "(\n    foo\n    bar\n    baz\n    quux\n    spam\n    eggs\n)"
+        "(foo bar\n     baz\n     quux\n     spam\n     eggs)",
+    [head_only, 0, false] sexpr![(foo)] => "(foo)"
+}
+
+#[test]
+fn hanging_alignment() {
+    assert_eq!(
+        render("(foo (bar baz) (quux spam eggs))", &Options::new(), 20),
+        "(foo (bar baz)\n     (quux spam\n           eggs))"
+    );
+}
+
+#[test]
+fn data_lists() {
+    assert_eq!(render("(1 2 3)", &Options::new(), 3), "(1\n 2\n 3)");
+    assert_eq!(
+        render("((a b) (c d))", &Options::new(), 6),
+        "((a b)\n (c d))"
+    );
+}
+
+#[test]
+fn body_rules() {
+    let opts = Options::new();
+    assert_eq!(
+        render(r#"(defun square (x) "Squares X." (* x x))"#, &opts, 30),
+        "(defun square (x)\n  \"Squares X.\"\n  (* x x))"
+    );
+    assert_eq!(
+        render("(let ((x 1) (y 2)) (+ x y))", &opts, 15),
+        "(let ((x 1)\n      (y 2))\n  (+ x y))"
+    );
+    assert_eq!(
+        render("(map (lambda (x) (* x x)) xs)", &opts, 20),
+        "(map (lambda (x)\n       (* x x))\n     xs)"
+    );
+    assert_eq!(render("(defun f (x) x)", &opts, 80), "(defun f (x) x)");
+}
+
+#[test]
+fn options() {
+    let opts = Options::without_rules().rule("define", 1).body_indent(4);
+    assert_eq!(
+        render("(define (f x) (g x) (h x))", &opts, 15),
+        "(define (f x)\n    (g x)\n    (h x))"
+    );
+    assert_eq!(
+        render("(defun f (x) x)", &opts, 10),
+        "(defun f\n       (x)\n       x)"
+    );
+    assert_eq!(
+        render("(begin (a) (b))", &Options::new().rule("begin", 0), 10),
+        "(begin\n  (a)\n  (b))"
+    );
+}
+
+#[test]
+fn multi_line_strings() {
+    assert_eq!(
+        render("(foo \"a\n b\" c)", &Options::new(), 5),
+        "(foo \"a\n b\"\n     c)"
+    );
+    assert_eq!(
+        render("(bar (foo \"a\n b\" c))", &Options::new(), 80),
+        "(bar (foo \"a\n b\" c))"
+    );
+}
+
+#[test]
+fn reader_syntax() {
+    let text = "; comment\n'(a b) `(c ,d ,@e) #(1 2) #\\( #\\  \"x\\\"y\" ; end\n";
+    let exprs = parse_all(text).unwrap();
+    let printed = exprs.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        printed,
+        [
+            "'(a b)",
+            "`(c ,d ,@e)",
+            "#(1 2)",
+            "#\\(",
+            "#\\ ",
+            "\"x\\\"y\""
+        ]
+    );
+    assert_eq!(parse_all("  ; nothing\n").unwrap(), []);
+}
+
+#[test]
+fn errors() {
+    let cases: &[(&str, usize)] = &[
+        ("", 0),
+        ("(a b", 4),
+        (")", 0),
+        ("(a))", 3),
+        ("a b", 2),
+        ("\"abc", 4),
+        ("'", 1),
+    ];
+    for &(text, offset) in cases {
+        assert_eq!(parse(text).map_err(|e| e.offset), Err(offset), "{:?}", text);
+    }
+    assert_eq!(
+        parse("(a b").unwrap_err().to_string(),
+        "expected ')' at byte 4"
+    );
+}
+
+#[test]
+fn deep_nesting() {
+    let sexpr = (0..64).fold(sexpr![foo], |inner, _| {
+        SExpr::List(vec![sexpr![bar], inner])
+    });
+    let expected = format!("{}foo{}", "(bar ".repeat(64), ")".repeat(64));
+    let doc: Doc = sexpr.to_doc();
+    assert_eq!(doc.display_opts(400, false).to_string(), expected);
+}
+
+#[test]
+fn nesting_limit() {
+    let nested = |n: usize| format!("{}a{}", "(".repeat(n), ")".repeat(n));
+    assert_eq!(render(&nested(128), &Options::new(), 80), nested(128));
+    assert_eq!(parse(&nested(129)).map_err(|e| e.offset), Err(128));
+    assert_eq!(
+        parse(&format!("'{}", nested(128))).map_err(|e| e.offset),
+        Err(128)
+    );
+    assert_eq!(
+        parse_all(&"'".repeat(100_000)).unwrap_err().to_string(),
+        "recursion limit exceeded at byte 128"
+    );
 }
@@ -0,0 +1,90 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use {Doc, Position, Region, RenderOptions};
+
+fn pos(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+/// `(+ 1 (* 2 3))`, with each node tagged by its position in a preorder walk.
+fn expr() -> Doc {
+    let mul = Doc::from("(* ")
+        .append(Doc::from("2").tagged(3))
+        .append(Doc::space())
+        .append(Doc::from("3").tagged(4))
+        .append(Doc::from(")"))
+        .nest(2)
+        .group()
+        .tagged(2);
+    Doc::from("(+ ")
+        .append(Doc::from("1").tagged(1))
+        .append(Doc::space())
+        .append(mul)
+        .append(Doc::from(")"))
+        .nest(2)
+        .group()
+        .tagged(0)
+}
+
+#[test]
+fn flat() {
+    let map = expr().source_map(RenderOptions::new(80));
+    assert_eq!(map.text, "(+ 1 (* 2 3))");
+    let ranges: Vec<_> = map
+        .regions
+        .iter()
+        .map(|r| (r.id, r.bytes.clone()))
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![(0, 0..13), (1, 3..4), (2, 5..12), (3, 8..9), (4, 10..11)]
+    );
+}
+
+#[test]
+fn broken() {
+    let map = expr().source_map(RenderOptions::new(10));
+    assert_eq!(map.text, "(+ 1\n  (* 2 3))");
+    assert_eq!(
+        map.regions[2],
+        Region {
+            id: 2,
+            bytes: 7..14,
+            start: pos(1, 2),
+            end: pos(1, 9),
+        }
+    );
+    assert_eq!(map.regions[0].end, pos(1, 10));
+    for r in &map.regions {
+        assert!(map.text.is_char_boundary(r.bytes.start));
+        assert!(map.text.is_char_boundary(r.bytes.end));
+    }
+}
+
+#[test]
+fn spanning_lines() {
+    let map = expr().source_map(RenderOptions::new(4));
+    assert_eq!(map.text, "(+ 1\n  (* 2\n    3))");
+    let r = &map.regions[2];
+    assert_eq!((r.start, r.end), (pos(1, 2), pos(2, 6)));
+    assert_eq!(&map.text[r.bytes.clone()], "(* 2\n    3)");
+}
+
+#[test]
+fn regions_at() {
+    let map = expr().source_map(RenderOptions::new(80));
+    let ids: Vec<_> = map.regions_at(8).map(|r| r.id).collect();
+    assert_eq!(ids, vec![0, 2, 3]);
+    assert_eq!(map.regions_at(13).count(), 0);
+}
+
+#[test]
+fn matches_render_fmt() {
+    for w in 1..16 {
+        let opts = RenderOptions::new(w);
+        let mut out = String::new();
+        expr().render_fmt(&mut out, opts).unwrap();
+        assert_eq!(expr().source_map(opts).text, out);
+    }
+}
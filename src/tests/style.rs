@@ -0,0 +1,74 @@
+use {Colour, Doc, Line, Style};
+
+fn layered() -> Doc {
+    Doc::from("/* ")
+        .append(Doc::text("TODO", Style::new().bold()))
+        .append(Doc::from(" */"))
+        .style(Colour::Black.dimmed())
+}
+
+#[test]
+fn nested_styles_compose() {
+    let doc = Doc::text("x", Style::new().bold()).style(Colour::Red.normal());
+    assert_eq!(
+        doc.display_opts(80, true).to_string(),
+        Colour::Red.bold().paint("x").to_string()
+    );
+}
+
+#[test]
+fn inner_colours_override() {
+    let doc = Doc::text("x", Colour::Blue.normal()).style(Colour::Red.on(Colour::White));
+    assert_eq!(
+        doc.display_opts(80, true).to_string(),
+        Colour::Blue.on(Colour::White).paint("x").to_string()
+    );
+}
+
+#[test]
+fn reset_style() {
+    let doc = Doc::from("a")
+        .append(Doc::text("b", Style::new().bold()).reset_style())
+        .append(Doc::from("c").reset_style())
+        .style(Colour::Red.normal());
+    assert_eq!(
+        doc.display_opts(80, true).to_string(),
+        format!(
+            "{}{}c",
+            Colour::Red.paint("a"),
+            Style::new().bold().paint("b")
+        )
+    );
+}
+
+#[test]
+fn layout_composes() {
+    assert_eq!(
+        layered().layout(80),
+        vec![Line {
+            indent: 0,
+            spans: vec![
+                ("/* ".to_string(), Colour::Black.dimmed()),
+                ("TODO".to_string(), Colour::Black.dimmed().bold()),
+                (" */".to_string(), Colour::Black.dimmed()),
+            ],
+        }]
+    );
+}
+
+#[test]
+fn html_reset_closes_spans() {
+    let doc = Doc::from("a")
+        .append(Doc::text("b", Style::new().bold()).reset_style())
+        .append(Doc::from("c"))
+        .style(Colour::Red.normal())
+        .link("u");
+    assert_eq!(
+        doc.html(80).to_string(),
+        concat!(
+            "<a href=\"u\"><span style=\"color:#800000\">a</span>",
+            "<span style=\"font-weight:bold\">b</span>",
+            "<span style=\"color:#800000\">c</span></a>"
+        )
+    );
+}
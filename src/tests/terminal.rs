@@ -0,0 +1,68 @@
+use {ColorLevel, RenderOptions, Terminal};
+
+fn term(is_tty: bool, size: Option<usize>, vars: &[(&str, &str)]) -> Terminal {
+    Terminal {
+        vars: vars
+            .iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        is_tty,
+        size,
+    }
+}
+
+#[test]
+fn width() {
+    assert_eq!(term(false, None, &[]).width(), 80);
+    assert_eq!(term(true, Some(132), &[]).width(), 132);
+    assert_eq!(term(true, Some(132), &[("COLUMNS", "100")]).width(), 100);
+    assert_eq!(term(false, None, &[("COLUMNS", "40")]).width(), 40);
+    assert_eq!(term(true, Some(132), &[("COLUMNS", "0")]).width(), 132);
+    assert_eq!(term(false, None, &[("COLUMNS", "wide")]).width(), 80);
+}
+
+#[test]
+fn color_needs_a_tty() {
+    assert_eq!(term(false, None, &[]).color(), ColorLevel::None);
+    assert_eq!(term(true, None, &[]).color(), ColorLevel::Ansi16);
+}
+
+#[test]
+fn color_levels() {
+    let level = |vars: &[(&str, &str)]| term(true, None, vars).color();
+    assert_eq!(level(&[("TERM", "xterm-256color")]), ColorLevel::Ansi256);
+    assert_eq!(
+        level(&[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]),
+        ColorLevel::TrueColor
+    );
+    assert_eq!(level(&[("COLORTERM", "24bit")]), ColorLevel::TrueColor);
+    assert_eq!(level(&[("TERM", "dumb")]), ColorLevel::None);
+}
+
+#[test]
+fn color_precedence() {
+    let level = |is_tty, vars: &[(&str, &str)]| term(is_tty, None, vars).color();
+    assert_eq!(level(true, &[("NO_COLOR", "1")]), ColorLevel::None);
+    assert_eq!(level(true, &[("NO_COLOR", "")]), ColorLevel::Ansi16);
+    assert_eq!(level(true, &[("CLICOLOR", "0")]), ColorLevel::None);
+    assert_eq!(level(false, &[("CLICOLOR", "1")]), ColorLevel::None);
+    assert_eq!(level(false, &[("CLICOLOR_FORCE", "1")]), ColorLevel::Ansi16);
+    assert_eq!(level(false, &[("CLICOLOR_FORCE", "0")]), ColorLevel::None);
+    assert_eq!(
+        level(true, &[("CLICOLOR_FORCE", "1"), ("TERM", "dumb")]),
+        ColorLevel::Ansi16
+    );
+    assert_eq!(
+        level(false, &[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]),
+        ColorLevel::None
+    );
+}
+
+#[test]
+fn options() {
+    let t = term(true, Some(100), &[("TERM", "xterm-256color")]);
+    assert_eq!(
+        t.options(),
+        RenderOptions::new(100).color(ColorLevel::Ansi256)
+    );
+}
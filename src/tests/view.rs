@@ -0,0 +1,170 @@
+use proptest::prelude::*;
+
+use {Colour, Doc, RenderOptions, Style, View};
+
+#[test]
+fn is_empty() {
+    assert!(Doc::<()>::empty().is_empty());
+    assert!(Doc::<()>::from("").group().nest(2).is_empty());
+    assert!(Doc::<()>::empty().append(Doc::empty()).is_empty());
+    assert!(Doc::empty()
+        .style(Colour::Red.normal())
+        .link("https://example.com")
+        .is_empty());
+    assert!(!Doc::<()>::from("a").is_empty());
+    assert!(!Doc::<()>::split_point().is_empty());
+    assert!(!Doc::<()>::from("a").flat_alt(Doc::empty()).is_empty());
+    assert!(!Doc::<()>::column(|_| Doc::empty()).is_empty());
+}
+
+#[test]
+fn flat_width() {
+    let doc: Doc<()> = Doc::from("foo")
+        .append(Doc::space())
+        .append(Doc::from("bar").nest(4))
+        .append(Doc::from(",").flat_alt(Doc::empty()))
+        .group();
+    assert_eq!(doc.flat_width(), Some(7));
+    assert_eq!(Doc::<()>::empty().flat_width(), Some(0));
+    assert_eq!(Doc::<()>::from("a\nb").flat_width(), None);
+    assert_eq!(
+        Doc::<()>::from("ab")
+            .append(Doc::column(|k| Doc::from("x".repeat(k))))
+            .flat_width(),
+        Some(4)
+    );
+}
+
+#[test]
+fn contains_hard_line() {
+    assert!(!Doc::<()>::from("a")
+        .append(Doc::space())
+        .contains_hard_line());
+    assert!(Doc::<()>::from("a")
+        .append(Doc::line())
+        .contains_hard_line());
+    assert!(Doc::<()>::from("a\nb").group().contains_hard_line());
+    assert!(Doc::<()>::line()
+        .flat_alt(Doc::empty())
+        .contains_hard_line());
+    assert!(Doc::<()>::nesting(|_| Doc::line()).contains_hard_line());
+}
+
+#[test]
+fn view() {
+    let doc: Doc = Doc::from("a").style(Colour::Red.normal());
+    match doc.view() {
+        View::Annotate(style, x) => {
+            assert_eq!(*style, Colour::Red.normal());
+            assert_eq!(x.view(), View::Text("a"));
+        }
+        view => panic!("{:?}", view),
+    }
+    assert_eq!(Doc::<()>::space().view(), View::Line(Some(" ")));
+}
+
+/// Collects the text that each style applies to, innermost first.
+fn styled_regions(doc: &Doc, out: &mut Vec<(Style, String)>) {
+    match doc.view() {
+        View::Annotate(&style, x) => {
+            styled_regions(&x, out);
+            out.push((style, x.display_opts(usize::MAX, false).to_string()));
+        }
+        View::Append(x, y) | View::FlatAlt(x, y) => {
+            styled_regions(&x, out);
+            styled_regions(&y, out);
+        }
+        View::Group(x) | View::Link(_, x) | View::Nest(_, x) | View::Reset(x) | View::Tag(_, x) => {
+            styled_regions(&x, out)
+        }
+        View::Column(_) | View::Line(_) | View::Nesting(_) | View::Text(_) => {}
+    }
+}
+
+#[test]
+fn collecting_styled_regions() {
+    let bold = Style::new().bold();
+    let doc = Doc::from("let ")
+        .append(Doc::from("x").style(Colour::Blue.normal()))
+        .append(Doc::from(" = 1;"))
+        .style(bold);
+    let mut regions = Vec::new();
+    styled_regions(&doc, &mut regions);
+    assert_eq!(
+        regions,
+        [
+            (Colour::Blue.normal(), "x".to_string()),
+            (bold, "let x = 1;".to_string())
+        ]
+    );
+}
+
+/// Rewrites a `Doc`, upper-casing its text.
+fn shout(doc: &Doc<()>) -> Doc<()> {
+    match doc.view() {
+        View::Text(s) => Doc::from(s.to_uppercase()),
+        View::Append(x, y) => shout(&x).append(shout(&y)),
+        View::Group(x) => shout(&x).group(),
+        View::Nest(n, x) => Doc::from(View::Nest(n, shout(&x))),
+        view => Doc::from(view),
+    }
+}
+
+#[test]
+fn rewriting() {
+    let doc = Doc::from("foo")
+        .append(Doc::space())
+        .append(Doc::from("bar"))
+        .nest(2)
+        .group();
+    let mut out = String::new();
+    shout(&doc)
+        .render_fmt(&mut out, RenderOptions::new(5))
+        .unwrap();
+    assert_eq!(out, "FOO\n  BAR");
+}
+
+fn arb_doc() -> BoxedStrategy<Doc> {
+    let leaf = prop_oneof![
+        "[a-z]{0,8}".prop_map(Doc::from),
+        Just(Doc::space()),
+        Just(Doc::split_point()),
+        Just(Doc::line()),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(|ds| ds.into_iter().collect()),
+            inner.clone().prop_map(Doc::group),
+            (inner.clone(), 0usize..4).prop_map(|(d, n)| d.nest(n)),
+            (inner.clone(), inner.clone()).prop_map(|(x, y)| x.flat_alt(y)),
+            inner.prop_map(|d| d.style(Colour::Green.normal())),
+        ]
+    })
+    .boxed()
+}
+
+proptest! {
+    #[test]
+    fn view_round_trips(ref doc in arb_doc()) {
+        assert_eq!(&Doc::from(doc.view()), doc);
+    }
+
+    #[test]
+    fn flat_width_matches_rendering(ref doc in arb_doc()) {
+        let s = doc.clone().group().display_opts(usize::MAX, false).to_string();
+        match doc.flat_width() {
+            Some(width) => assert_eq!(s.len(), width),
+            None => assert!(s.contains('\n')),
+        }
+    }
+
+    #[test]
+    fn helpers_agree(ref doc in arb_doc()) {
+        if !doc.contains_hard_line() {
+            assert!(doc.flat_width().is_some());
+        }
+        if doc.is_empty() {
+            assert_eq!(doc.display_opts(0, false).to_string(), "");
+        }
+    }
+}
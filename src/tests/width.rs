@@ -0,0 +1,57 @@
+use width::width;
+use Doc;
+
+fn words() -> Doc {
+    Doc::space()
+        .join(vec!["日本語", "の", "識別子"].into_iter().map(Doc::from))
+        .group()
+}
+
+#[test]
+fn ascii() {
+    assert_eq!(width("hello"), 5);
+}
+
+#[cfg(not(feature = "unicode-width"))]
+#[test]
+fn chars() {
+    assert_eq!(width("日本語"), 3);
+    assert_eq!(width("e\u{301}"), 2);
+}
+
+#[cfg(not(feature = "unicode-width"))]
+#[test]
+fn wide_text_by_chars() {
+    assert_eq!(
+        words().display_opts(10, false).to_string(),
+        "日本語 の 識別子"
+    );
+}
+
+#[cfg(feature = "unicode-width")]
+#[test]
+fn wide_chars() {
+    assert_eq!(width("日本語"), 6);
+}
+
+#[cfg(feature = "unicode-width")]
+#[test]
+fn combining_marks() {
+    assert_eq!(width("e\u{301}"), 1);
+}
+
+#[cfg(feature = "unicode-width")]
+#[test]
+fn grapheme_clusters() {
+    // A family emoji, made of three emoji joined by zero-width joiners.
+    assert_eq!(width("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}"), 2);
+}
+
+#[cfg(feature = "unicode-width")]
+#[test]
+fn wide_text_breaks() {
+    assert_eq!(
+        words().display_opts(10, false).to_string(),
+        "日本語\nの\n識別子"
+    );
+}
@@ -0,0 +1,204 @@
+//! Inspecting the structure of a `Doc`.
+
+use alloc::string::ToString;
+
+use width::width;
+use {Doc, DocInner, Ptr, Thunk};
+
+/// One node of a `Doc`, as returned by `Doc::view`.
+///
+/// The parts of the node are `Doc`s themselves, which share their contents
+/// with the original, so views are cheap to make. A `View` can be turned back
+/// into a `Doc` with `Doc::from`, which makes it possible to rewrite a `Doc`
+/// by viewing it, rewriting the parts, and rebuilding the node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum View<'a, A: 'a> {
+    /// A `Doc` with an annotation, from `Doc::annotate`.
+    Annotate(&'a A, Doc<A>),
+
+    /// Two `Doc`s one after the other, from `Doc::append`.
+    Append(Doc<A>, Doc<A>),
+
+    /// A `Doc` built from the column it starts at, from `Doc::column`.
+    Column(Deferred<A>),
+
+    /// A `Doc` used when the enclosing group is broken (the first) and one
+    /// used when it is flattened (the second), from `Doc::flat_alt`.
+    FlatAlt(Doc<A>, Doc<A>),
+
+    /// A group, from `Doc::group`.
+    Group(Doc<A>),
+
+    /// A newline, along with the string it collapses to, or `None` if it never
+    /// collapses, from `Doc::line_or` and `Doc::line`.
+    Line(Option<&'static str>),
+
+    /// A hyperlink to a URL, from `Doc::link`.
+    Link(&'a str, Doc<A>),
+
+    /// A `Doc` with its indentation changed by the given amount, from
+    /// `Doc::nest`. The amount may be negative.
+    Nest(isize, Doc<A>),
+
+    /// A `Doc` built from the current indentation, from `Doc::nesting`.
+    Nesting(Deferred<A>),
+
+    /// A `Doc` that the enclosing annotations don't apply to, from
+    /// `Doc::reset_style`.
+    Reset(Doc<A>),
+
+    /// A `Doc` tagged with an ID, from `Doc::tagged`.
+    Tag(usize, Doc<A>),
+
+    /// A string without newlines.
+    Text(&'a str),
+}
+
+/// The function behind a `Doc` built by `Doc::column` or `Doc::nesting`.
+#[derive(Debug, PartialEq)]
+pub struct Deferred<A>(Thunk<A>);
+
+impl<A> Clone for Deferred<A> {
+    fn clone(&self) -> Deferred<A> {
+        Deferred(self.0.clone())
+    }
+}
+
+impl<A> Deferred<A> {
+    /// Calls the function with a column or an indentation, returning the
+    /// `Doc` it builds.
+    pub fn call(&self, n: usize) -> Doc<A> {
+        self.0.call(n)
+    }
+}
+
+impl<'a, A: Clone> From<View<'a, A>> for Doc<A> {
+    fn from(view: View<'a, A>) -> Doc<A> {
+        let inner = match view {
+            View::Annotate(a, x) => DocInner::Annotate(a.clone(), x.inner),
+            View::Append(x, y) => DocInner::Append(x.inner, y.inner),
+            View::Column(f) => DocInner::Column(f.0),
+            View::FlatAlt(x, y) => DocInner::FlatAlt(x.inner, y.inner),
+            View::Group(x) => DocInner::Group(x.inner),
+            View::Line(s) => DocInner::Line(s),
+            View::Link(url, x) => DocInner::Link(url.to_string().into(), x.inner),
+            View::Nest(n, x) => DocInner::Nest(n, x.inner),
+            View::Nesting(f) => DocInner::Nesting(f.0),
+            View::Reset(x) => DocInner::Reset(x.inner),
+            View::Tag(id, x) => DocInner::Tag(id, x.inner),
+            View::Text(s) => DocInner::Text(s.to_string().into()),
+        };
+        Doc::from(inner)
+    }
+}
+
+impl<A> Doc<A> {
+    /// Returns the outermost node of the `Doc`.
+    pub fn view(&self) -> View<'_, A> {
+        let doc = |x: &Ptr<DocInner<A>>| Doc { inner: x.clone() };
+        match *self.inner {
+            DocInner::Annotate(ref a, ref x) => View::Annotate(a, doc(x)),
+            DocInner::Append(ref x, ref y) => View::Append(doc(x), doc(y)),
+            DocInner::Column(ref f) => View::Column(Deferred(f.clone())),
+            DocInner::FlatAlt(ref x, ref y) => View::FlatAlt(doc(x), doc(y)),
+            DocInner::Group(ref x) => View::Group(doc(x)),
+            DocInner::Line(s) => View::Line(s),
+            DocInner::Link(ref url, ref x) => View::Link(url, doc(x)),
+            DocInner::Nest(n, ref x) => View::Nest(n, doc(x)),
+            DocInner::Nesting(ref f) => View::Nesting(Deferred(f.clone())),
+            DocInner::Reset(ref x) => View::Reset(doc(x)),
+            DocInner::Tag(id, ref x) => View::Tag(id, doc(x)),
+            DocInner::Text(ref s) => View::Text(s),
+        }
+    }
+
+    /// Returns whether the `Doc` is always rendered as nothing, like
+    /// `Doc::empty()`. Links and annotations around nothing are empty, but
+    /// line breaks, and `Doc`s built by `Doc::column` or `Doc::nesting`, are
+    /// not.
+    pub fn is_empty(&self) -> bool {
+        let mut stack = vec![&self.inner];
+        while let Some(doc) = stack.pop() {
+            match **doc {
+                DocInner::Annotate(_, ref x)
+                | DocInner::Group(ref x)
+                | DocInner::Link(_, ref x)
+                | DocInner::Nest(_, ref x)
+                | DocInner::Reset(ref x)
+                | DocInner::Tag(_, ref x) => stack.push(x),
+                DocInner::Append(ref x, ref y) | DocInner::FlatAlt(ref x, ref y) => {
+                    stack.push(y);
+                    stack.push(x);
+                }
+                DocInner::Column(_) | DocInner::Line(_) | DocInner::Nesting(_) => return false,
+                DocInner::Text(ref s) => {
+                    if !s.is_empty() {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns the width of the `Doc` when it is laid out on one line,
+    /// starting at the first column, or `None` if it contains a line break
+    /// that can't be collapsed.
+    ///
+    /// This is the width the renderer compares against the page when deciding
+    /// whether a group fits. `Doc`s built by `Doc::nesting` are built with an
+    /// indentation of zero.
+    pub fn flat_width(&self) -> Option<usize> {
+        let mut k = 0;
+        let mut stack = vec![self.inner.clone()];
+        while let Some(doc) = stack.pop() {
+            match *doc {
+                DocInner::Annotate(_, ref x)
+                | DocInner::Group(ref x)
+                | DocInner::Link(_, ref x)
+                | DocInner::Nest(_, ref x)
+                | DocInner::Reset(ref x)
+                | DocInner::Tag(_, ref x)
+                | DocInner::FlatAlt(_, ref x) => stack.push(x.clone()),
+                DocInner::Append(ref x, ref y) => {
+                    stack.push(y.clone());
+                    stack.push(x.clone());
+                }
+                DocInner::Column(ref f) => stack.push(f.call(k).inner),
+                DocInner::Line(None) => return None,
+                DocInner::Line(Some(s)) => k += width(s),
+                DocInner::Nesting(ref f) => stack.push(f.call(0).inner),
+                DocInner::Text(ref s) => k += width(s),
+            }
+        }
+        Some(k)
+    }
+
+    /// Returns whether the `Doc` contains a line break that can't be
+    /// collapsed, from `Doc::line` or text with a newline in it.
+    ///
+    /// Both sides of a `Doc::flat_alt` are checked. `Doc`s built by
+    /// `Doc::column` and `Doc::nesting` are built with a column and
+    /// indentation of zero.
+    pub fn contains_hard_line(&self) -> bool {
+        let mut stack = vec![self.inner.clone()];
+        while let Some(doc) = stack.pop() {
+            match *doc {
+                DocInner::Annotate(_, ref x)
+                | DocInner::Group(ref x)
+                | DocInner::Link(_, ref x)
+                | DocInner::Nest(_, ref x)
+                | DocInner::Reset(ref x)
+                | DocInner::Tag(_, ref x) => stack.push(x.clone()),
+                DocInner::Append(ref x, ref y) | DocInner::FlatAlt(ref x, ref y) => {
+                    stack.push(y.clone());
+                    stack.push(x.clone());
+                }
+                DocInner::Column(ref f) | DocInner::Nesting(ref f) => stack.push(f.call(0).inner),
+                DocInner::Line(None) => return true,
+                DocInner::Line(Some(_)) | DocInner::Text(_) => {}
+            }
+        }
+        false
+    }
+}
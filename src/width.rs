@@ -0,0 +1,27 @@
+//! Measuring how many columns text takes up on a terminal.
+
+#[cfg(feature = "unicode-width")]
+use unicode_segmentation::UnicodeSegmentation;
+#[cfg(feature = "unicode-width")]
+use unicode_width::UnicodeWidthChar;
+
+/// Returns the number of columns the string takes up when displayed.
+///
+/// Each grapheme cluster is as wide as the widest character in it, so wide
+/// characters count as two columns, and combining marks and the rest of an
+/// emoji sequence count as none.
+#[cfg(feature = "unicode-width")]
+pub fn width(s: &str) -> usize {
+    s.graphemes(true)
+        .map(|g| g.chars().filter_map(|c| c.width()).max().unwrap_or(0))
+        .sum()
+}
+
+/// Returns the number of columns the string takes up when displayed.
+///
+/// Without the `unicode-width` feature, this is just the number of `char`s in
+/// the string.
+#[cfg(not(feature = "unicode-width"))]
+pub fn width(s: &str) -> usize {
+    s.chars().count()
+}
@@ -0,0 +1,42 @@
+//! Checks the layouts of the ML-like language used by the benchmarks.
+
+#![cfg(feature = "std")]
+
+#[macro_use]
+extern crate sparkly;
+
+#[path = "../benches/ml/mod.rs"]
+mod ml;
+
+use sparkly::Sparkly;
+
+#[test]
+fn small_ast() {
+    let ex = ml::small_ast();
+    assert_eq!(
+        ex.to_doc().display_opts(80, false).to_string(),
+        concat!(
+            "let s = (\\x y z. (x y (y z))) in\n",
+            "  let k = (\\x y. x) in\n",
+            "    let i = (\\x. x) in\n",
+            "      (i s k k i)"
+        )
+    );
+}
+
+#[test]
+fn medium_ast() {
+    let ex = ml::medium_ast();
+    assert_eq!(
+        ex.to_doc().display_opts(80, false).to_string(),
+        concat!(
+            "let s = (\\x y z. (x y (y z))) in\n",
+            "  let k = (\\x y. x) in\n",
+            "    let i = (\\x. x) in\n",
+            "      let rec even =\n",
+            "        (\\x. if (eq x zero) then true else (not (even (minus x one))))\n",
+            "      in\n",
+            "        (i (even five))"
+        )
+    );
+}